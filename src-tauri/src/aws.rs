@@ -1,4 +1,7 @@
+use crate::log_checkpoint::SearchProgress;
+use crate::log_sources::{LogNaming, LogSources};
 use crate::shared::{arn_to_name, cluster_arn_to_name, CommandError, Env, TrackedName};
+use crate::structured_logs::{self, FieldFilter, LogFields};
 use aws_config::{
     profile::{ProfileFileLoadError, ProfileSet},
     retry::RetryConfig,
@@ -159,6 +162,30 @@ pub struct LogEntry {
     pub timestamp: i64,
     pub ingestion_time: i64,
     pub message: String,
+    pub app: Option<String>,
+    pub env: Option<Env>,
+    #[serde(flatten)]
+    pub fields: LogFields,
+}
+
+impl LogEntry {
+    pub fn new(
+        log_stream_name: String,
+        app: Option<String>,
+        timestamp: i64,
+        ingestion_time: i64,
+        message: String,
+    ) -> Self {
+        LogEntry {
+            fields: structured_logs::parse(&message),
+            app,
+            env: None,
+            log_stream_name,
+            timestamp,
+            ingestion_time,
+            message,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -307,6 +334,26 @@ impl AwsConfigProvider {
             .collect()
     }
 
+    pub fn default_profiles(
+        &self,
+        app: &str,
+        env: &Env,
+    ) -> Result<(InfraProfile, SsoProfile), String> {
+        let sso_profile = self
+            .active_wombat_profile
+            .sso_profiles
+            .get(env)
+            .cloned()
+            .ok_or_else(|| format!("No sso profile for {env}"))?;
+        let infra_profile = sso_profile
+            .infra_profiles
+            .iter()
+            .find(|infra_profile| &infra_profile.env == env && infra_profile.app == app)
+            .cloned()
+            .ok_or_else(|| format!("No infra profile for {app} on {env}"))?;
+        Ok((infra_profile, sso_profile))
+    }
+
     pub async fn sso_config(&self, env: &Env) -> (String, aws_config::SdkConfig) {
         info!("getting sso_profile for env={env}");
         let sso_profile = self
@@ -843,6 +890,37 @@ pub async fn get_secret(
     Err(CommandError::new("secret", "Secret not found"))
 }
 
+pub async fn get_secrets_manager_secret(
+    config: &aws_config::SdkConfig,
+    secret_id: &str,
+) -> Result<String, CommandError> {
+    let secret_client = secretsmanager::Client::new(config);
+    info!("looking for secrets manager secret={secret_id}");
+    let secret = secret_client
+        .get_secret_value()
+        .secret_id(secret_id)
+        .send()
+        .await;
+
+    match secret {
+        Ok(secret) => secret
+            .secret_string()
+            .map(|value| value.to_owned())
+            .ok_or(CommandError::new("secret", "Secret has no string value")),
+        Err(err) => {
+            let error_str = err
+                .source()
+                .map(|s| s.to_string())
+                .unwrap_or(err.to_string());
+            warn!("failed to fetch secret {secret_id}, reason: {error_str}");
+            Err(CommandError::new(
+                "secret",
+                format!("Secret not found, reason: {error_str}"),
+            ))
+        }
+    }
+}
+
 pub async fn databases(config: &aws_config::SdkConfig) -> Vec<RdsInstance> {
     let mut there_is_more = true;
     let mut marker = None;
@@ -1429,6 +1507,317 @@ pub trait LogSearchMonitor: Send {
     fn success(&mut self, msg: String);
     fn error(&mut self, msg: String);
     fn message(&mut self, msg: String);
+    fn checkpoint(&mut self, _progress: &SearchProgress) {}
+    fn warning(&mut self, warning: SearchWarning) {
+        self.message(warning.message);
+    }
+}
+
+/// What happens when a search finds more entries than its limit.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitPolicy {
+    /// Fail the search, what every search did before limits were configurable.
+    #[default]
+    Abort,
+    /// Stop at the limit and keep the first entries.
+    First,
+    /// Search the whole range and keep the last entries.
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchLimit {
+    pub max: usize,
+    #[serde(default)]
+    pub policy: LimitPolicy,
+}
+
+pub const DEFAULT_SEARCH_LIMIT: usize = 10000;
+
+impl SearchLimit {
+    pub fn abort(max: usize) -> Self {
+        SearchLimit {
+            max,
+            policy: LimitPolicy::Abort,
+        }
+    }
+
+    /// Limit of a search by where its raw entries go, neither a dump file nor a summary shown
+    /// instead of the entries keeps them in memory.
+    pub fn for_search(
+        limit: Option<SearchLimit>,
+        to_file: bool,
+        summary_only: bool,
+    ) -> Option<SearchLimit> {
+        match to_file || summary_only {
+            false => Some(limit.unwrap_or(SearchLimit::abort(DEFAULT_SEARCH_LIMIT))),
+            true => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchWarning {
+    pub message: String,
+    pub log_count: usize,
+    /// Where a truncated filter search stopped, it can be continued from there.
+    pub resume_from: Option<SearchProgress>,
+}
+
+fn keep_last(tail: &mut std::collections::VecDeque<LogEntry>, logs: Vec<LogEntry>, max: usize) {
+    tail.extend(logs);
+    while tail.len() > max {
+        tail.pop_front();
+    }
+}
+
+const LOG_FETCH_CONCURRENCY: usize = 4;
+const THROTTLING_MAX_RETRIES: u32 = 6;
+const THROTTLING_BASE_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
+struct StreamChunk {
+    group_name: String,
+    /// `None` searches the whole log group.
+    stream_names: Option<Vec<String>>,
+}
+
+type LogPage = Result<Vec<LogEntry>, String>;
+
+type FilterLogEventsResult = Result<
+    cloudwatchlogs::operation::filter_log_events::FilterLogEventsOutput,
+    cloudwatchlogs::error::SdkError<
+        cloudwatchlogs::operation::filter_log_events::FilterLogEventsError,
+    >,
+>;
+
+/// Retries throttled FilterLogEvents calls with exponential backoff.
+async fn filter_log_events_with_backoff<F, Fut>(
+    group_name: &str,
+    mut send: F,
+) -> FilterLogEventsResult
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = FilterLogEventsResult>,
+{
+    let mut attempt = 0;
+    loop {
+        let response = send().await;
+        let throttled = response.as_ref().is_err_and(|err| {
+            err.as_service_error()
+                .and_then(|err| err.meta().code())
+                .is_some_and(|code| code == "ThrottlingException")
+        });
+        if !throttled || attempt >= THROTTLING_MAX_RETRIES {
+            return response;
+        }
+        attempt += 1;
+        let delay = THROTTLING_BASE_DELAY * 2u32.pow(attempt);
+        warn!("filter_log_events throttled for {group_name}, retry {attempt} in {delay:?}");
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn fetch_chunk(
+    client: cloudwatchlogs::Client,
+    chunk: StreamChunk,
+    filter: Option<String>,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    naming: Arc<LogNaming>,
+    permits: Arc<tokio::sync::Semaphore>,
+    pages: tokio::sync::mpsc::Sender<LogPage>,
+) {
+    let mut marker = None;
+    loop {
+        // A page is fetched only once the merge took the previous one, the merge needs the head
+        // of every chunk but nothing beyond it is read ahead.
+        let Ok(slot) = pages.reserve().await else {
+            return;
+        };
+        let request = client
+            .filter_log_events()
+            .set_log_group_name(Some(chunk.group_name.to_owned()))
+            .set_log_stream_names(chunk.stream_names.clone())
+            .set_next_token(marker.clone())
+            .set_filter_pattern(filter.clone())
+            .set_start_time(Some(start_timestamp))
+            .set_end_time(Some(end_timestamp));
+        let logs_response = filter_log_events_with_backoff(&chunk.group_name, || {
+            let request = request.clone();
+            let permits = permits.clone();
+            async move {
+                // Permit is held only for the request, so chunks waiting on the merge do not block others.
+                let _permit = permits.acquire().await.expect("Log fetch semaphore closed");
+                request.send().await
+            }
+        })
+        .await;
+
+        let log_response_data = match logs_response {
+            Ok(data) => data,
+            Err(err) => {
+                let message = err
+                    .into_service_error()
+                    .meta()
+                    .message()
+                    .unwrap_or("")
+                    .to_owned();
+                slot.send(Err(message));
+                return;
+            }
+        };
+        marker = log_response_data.next_token().map(|m| m.to_owned());
+        let mut logs: Vec<LogEntry> = log_response_data
+            .events
+            .unwrap_or_default()
+            .into_iter()
+            .map(|event| {
+                let log_stream_name = event.log_stream_name.unwrap_or_default();
+                let app = naming.app_of(&log_stream_name);
+                LogEntry::new(
+                    log_stream_name,
+                    app,
+                    event.timestamp.unwrap_or_default(),
+                    event.ingestion_time.unwrap_or_default(),
+                    event.message.unwrap_or_default(),
+                )
+            })
+            .collect();
+        logs.sort_by_key(|log| log.timestamp);
+        slot.send(Ok(logs));
+        if marker.is_none() {
+            return;
+        }
+    }
+}
+
+/// Buffered pages of one chunk, entries are consumed in timestamp order.
+///
+/// Pages are sorted on their own, but FilterLogEvents only roughly orders them across pages.
+/// An entry is released once a later page starts after it, so an entry arriving late within
+/// that window is still merged in order.
+struct ChunkFeed {
+    pages: tokio::sync::mpsc::Receiver<LogPage>,
+    buffer: std::collections::VecDeque<LogEntry>,
+    /// First timestamp of the latest page, entries older than it are ready.
+    watermark: i64,
+    done: bool,
+}
+
+impl ChunkFeed {
+    fn new(pages: tokio::sync::mpsc::Receiver<LogPage>) -> ChunkFeed {
+        ChunkFeed {
+            pages,
+            buffer: std::collections::VecDeque::new(),
+            watermark: i64::MIN,
+            done: false,
+        }
+    }
+
+    fn ready(&self) -> Option<&LogEntry> {
+        self.buffer
+            .front()
+            .filter(|log| self.done || log.timestamp < self.watermark)
+    }
+
+    fn push_page(&mut self, logs: Vec<LogEntry>) {
+        let Some(first) = logs.first() else {
+            return;
+        };
+        self.watermark = self.watermark.max(first.timestamp);
+        self.buffer.extend(logs);
+        self.buffer
+            .make_contiguous()
+            .sort_by_key(|log| log.timestamp);
+    }
+
+    async fn fill(&mut self) -> Result<(), String> {
+        while self.ready().is_none() && !self.done {
+            match self.pages.recv().await {
+                Some(Ok(logs)) => self.push_page(logs),
+                Some(Err(message)) => return Err(message),
+                None => self.done = true,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Takes the oldest ready entries across feeds until one of them needs more pages.
+fn merge_ready(feeds: &mut [ChunkFeed]) -> Vec<LogEntry> {
+    let mut batch = Vec::new();
+    loop {
+        let next = feeds
+            .iter()
+            .enumerate()
+            .filter_map(|(index, feed)| feed.ready().map(|log| (log.timestamp, index)))
+            .min();
+        let Some((_, index)) = next else {
+            return batch;
+        };
+        let feed = &mut feeds[index];
+        batch.extend(feed.buffer.pop_front());
+        if feed.ready().is_none() && !feed.done {
+            return batch;
+        }
+    }
+}
+
+/// Drops entries a resumed search delivered before its checkpoint, the search restarts at the
+/// checkpoint timestamp.
+struct ResumeSkip {
+    timestamp: Option<i64>,
+    remaining: usize,
+}
+
+impl ResumeSkip {
+    fn new(progress: &SearchProgress) -> ResumeSkip {
+        ResumeSkip {
+            timestamp: progress.last_timestamp,
+            remaining: progress.entries_at_last_timestamp,
+        }
+    }
+
+    fn apply(&mut self, logs: &mut Vec<LogEntry>) {
+        if self.remaining == 0 {
+            return;
+        }
+        logs.retain(|log| {
+            let delivered = self.remaining > 0 && Some(log.timestamp) == self.timestamp;
+            if delivered {
+                self.remaining -= 1;
+            }
+            !delivered
+        });
+        // Past the checkpoint timestamp nothing else was delivered before.
+        if logs.iter().any(|log| Some(log.timestamp) != self.timestamp) {
+            self.remaining = 0;
+        }
+    }
+
+    /// Checkpoints count delivered entries only, the field filter runs before delivered ones are
+    /// skipped.
+    fn apply_matching(&mut self, logs: &mut Vec<LogEntry>, field_filter: Option<&FieldFilter>) {
+        if let Some(field_filter) = field_filter {
+            logs.retain(|log| field_filter.matches(log));
+        }
+        self.apply(logs);
+    }
+}
+
+fn advance_progress(progress: &mut SearchProgress, logs: &[LogEntry]) {
+    for log in logs.iter() {
+        if progress.last_timestamp == Some(log.timestamp) {
+            progress.entries_at_last_timestamp += 1;
+        } else {
+            progress.last_timestamp = Some(log.timestamp);
+            progress.entries_at_last_timestamp = 1;
+        }
+    }
+    progress.log_count += logs.len();
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1436,163 +1825,973 @@ pub async fn find_logs(
     config: &aws_config::SdkConfig,
     env: Env,
     apps: Vec<String>,
+    sources: &LogSources,
     start_timestamp: i64,
     end_timestamp: i64,
     filter: Option<String>,
+    field_filter: Option<&FieldFilter>,
     log_search_monitor: Arc<tokio::sync::Mutex<dyn LogSearchMonitor>>,
-    limit: Option<usize>,
+    limit: Option<SearchLimit>,
+    resume_from: Option<SearchProgress>,
 ) -> Result<usize, CommandError> {
     let client = cloudwatchlogs::Client::new(config);
-    let response = client
-        .describe_log_groups()
-        .set_log_group_name_prefix(Some(format!("dsi-{env}-").to_owned()))
-        .send()
-        .await;
+    let groups = match sources.group_names(&client, &env, &apps).await {
+        Ok(groups) => groups,
+        Err(message) => {
+            log_search_monitor.lock().await.error(message.clone());
+            return Err(CommandError::new("find_logs", message));
+        }
+    };
+    let apps_dbg_str = apps.join("|");
 
-    let response_data = response.unwrap();
-    let apps_dbg_str = format!("web/{}/", &apps.join("|"));
-
-    let groups = response_data.log_groups();
-    let mut log_count: usize = 0;
+    let mut progress = resume_from.unwrap_or_default();
+    let mut log_count = progress.log_count;
+    // Entries at the checkpoint timestamp were already delivered, the search restarts at it.
+    let start_timestamp = progress.last_timestamp.unwrap_or(start_timestamp);
+    let mut resume_skip = ResumeSkip::new(&progress);
+    let mut tail = std::collections::VecDeque::new();
 
     let search_string = filter.clone().unwrap_or(String::from("<empty>"));
-    let mut stream_names = Vec::new();
     info!("limit: {:?}", &limit);
     {
         let mut notifier = log_search_monitor.lock().await;
         notifier.message(String::from("Search log streams in progress..."));
     }
-    for group in groups {
-        let group_name = group.log_group_name().unwrap_or_default();
+    let mut stream_count = 0;
+    let mut chunks = Vec::new();
+    for group_name in groups.iter() {
         info!("log group: {}", &group_name);
+        info!("Searching for {search_string} in {apps_dbg_str}");
+        // Explicit log groups are searched as a whole, without narrowing to streams.
+        if sources.uses_explicit_groups() {
+            chunks.push(StreamChunk {
+                group_name: group_name.to_owned(),
+                stream_names: None,
+            });
+            continue;
+        }
+        let stream_names = match find_stream_names(
+            &client,
+            group_name,
+            &apps,
+            sources,
+            start_timestamp,
+            end_timestamp,
+            log_search_monitor.clone(),
+        )
+        .await
         {
-            info!("Searching for {search_string} in {apps_dbg_str}");
-            let log_streams_result = find_stream_names(
-                &client,
-                group_name,
-                &apps,
+            Ok(names) => names,
+            Err(error) => {
+                error!("search for log streams failed, cause={error}");
+                let mut notifier = log_search_monitor.lock().await;
+                notifier.error(error);
+                return Result::Ok(0);
+            }
+        };
+        info!("found log streams: [{}]", &stream_names.join(","));
+        stream_count += stream_names.len();
+        chunks.extend(stream_names.chunks(100).map(|chunk| StreamChunk {
+            group_name: group_name.to_owned(),
+            stream_names: Some(chunk.to_vec()),
+        }));
+    }
+
+    if chunks.is_empty() {
+        info!("log streams empty, returning");
+        let mut notifier = log_search_monitor.lock().await;
+        notifier.success(String::from(
+            "No log streams found having logs in given timeframe.",
+        ));
+        return Result::Ok(0);
+    }
+    {
+        let mut notifier = log_search_monitor.lock().await;
+        notifier.message(format!("Searching in {stream_count} log stream(s)..."));
+    }
+
+    let naming = Arc::new(sources.naming.clone());
+    let permits = Arc::new(tokio::sync::Semaphore::new(LOG_FETCH_CONCURRENCY));
+    // Dropping the set aborts fetches still running when the search ends early.
+    let mut fetches = tokio::task::JoinSet::new();
+    let mut feeds: Vec<ChunkFeed> = chunks
+        .into_iter()
+        .map(|chunk| {
+            let (sender, receiver) = tokio::sync::mpsc::channel(1);
+            fetches.spawn(fetch_chunk(
+                client.clone(),
+                chunk,
+                filter.clone(),
                 start_timestamp,
                 end_timestamp,
-                log_search_monitor.clone(),
-            )
-            .await;
-            (match log_streams_result {
-                Ok(names) => stream_names = names,
-                Err(error) => {
-                    error!("search for log streams failed, cause={error}");
-                    let mut notifier = log_search_monitor.lock().await;
-                    notifier.error(error);
-                    return Result::Ok(0);
-                }
-            });
+                naming.clone(),
+                permits.clone(),
+                sender,
+            ));
+            ChunkFeed::new(receiver)
+        })
+        .collect();
 
-            {
+    loop {
+        for feed in feeds.iter_mut() {
+            if let Err(message) = feed.fill().await {
                 let mut notifier = log_search_monitor.lock().await;
-                if stream_names.is_empty() {
-                    info!("log streams empty, returning");
-                    notifier.success(String::from(
-                        "No log streams found having logs in given timeframe.",
-                    ));
-                    return Result::Ok(0);
+                notifier.error(format!("Error: {}", &message).to_owned());
+                return Result::Err(CommandError {
+                    message,
+                    command: "find_logs".to_owned(),
+                });
+            }
+        }
+        let mut logs = merge_ready(&mut feeds);
+        if logs.is_empty() {
+            break;
+        }
+        // Entries dropped by the field filter don't count toward the limit.
+        resume_skip.apply_matching(&mut logs, field_filter);
+        let limit_reached = limit.is_some_and(|limit| log_count + logs.len() > limit.max);
+        if let Some(SearchLimit {
+            max,
+            policy: LimitPolicy::First,
+        }) = limit
+        {
+            logs.truncate(max.saturating_sub(log_count));
+        }
+        advance_progress(&mut progress, &logs);
+        info!("found {} logs", &logs.len());
+        log_count += logs.len();
+
+        let mut notifier = log_search_monitor.lock().await;
+        match limit {
+            Some(SearchLimit {
+                max,
+                policy: LimitPolicy::Last,
+            }) => keep_last(&mut tail, logs, max),
+            _ => notifier.notify(logs),
+        }
+        notifier.message(format!(
+            "Searching in {stream_count} log stream(s), found {log_count} logs..."
+        ));
+        notifier.checkpoint(&progress);
+        match limit {
+            Some(SearchLimit {
+                max,
+                policy: LimitPolicy::Abort,
+            }) if limit_reached => {
+                let msg = format!(
+                    "Search in {stream_count} log stream(s) aborted, found {} logs. Reached limit of {} logs.",
+                    &log_count, &max
+                )
+                .to_owned();
+                warn!("exceeded max log count, Limit {log_count}/{max}");
+                notifier.error(msg.to_owned());
+                return Result::Err(CommandError {
+                    message: msg,
+                    command: "find_logs".to_owned(),
+                });
+            }
+            Some(SearchLimit {
+                max,
+                policy: LimitPolicy::First,
+            }) if limit_reached => {
+                info!("log search truncated at {max} logs");
+                notifier.warning(SearchWarning {
+                    message: format!(
+                        "Showing the first {max} logs, the search stopped at the limit."
+                    ),
+                    log_count,
+                    resume_from: Some(progress.clone()),
+                });
+                notifier.success(format!(
+                    "Search in {stream_count} log stream(s) stopped at the limit of {max} logs."
+                ));
+                return Result::Ok(log_count);
+            }
+            _ => {}
+        }
+    }
+    info!("logs search finished");
+
+    let mut notifier = log_search_monitor.lock().await;
+    if let Some(SearchLimit {
+        max,
+        policy: LimitPolicy::Last,
+    }) = limit
+    {
+        let kept = tail.len();
+        notifier.notify(tail.into());
+        if log_count > max {
+            notifier.warning(SearchWarning {
+                message: format!("Showing the last {kept} of {log_count} logs."),
+                log_count,
+                resume_from: None,
+            });
+        }
+    }
+    notifier.success(format!(
+        "Search in {stream_count} log stream(s) done. Found {log_count} logs."
+    ));
+    Result::Ok(log_count)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSearchMode {
+    #[default]
+    Filter,
+    Insights,
+}
+
+const INSIGHTS_MAX_LIMIT: i32 = 10000;
+const INSIGHTS_MAX_LOG_GROUPS: usize = 50;
+const INSIGHTS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1000);
+const INSIGHTS_DEFAULT_QUERY: &str =
+    "fields @timestamp, @message, @logStream | sort @timestamp asc";
+
+struct InsightsQueryGuard {
+    client: cloudwatchlogs::Client,
+    query_id: String,
+    finished: bool,
+}
+
+impl Drop for InsightsQueryGuard {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let client = self.client.clone();
+        let query_id = self.query_id.clone();
+        info!("Stopping insights query={query_id}");
+        tokio::task::spawn(async move {
+            if let Err(e) = client.stop_query().query_id(&query_id).send().await {
+                warn!("Failed to stop insights query={query_id}, {e}");
+            }
+        });
+    }
+}
+
+fn insights_query(apps: &[String], sources: &LogSources, query: Option<String>) -> String {
+    let query = query
+        .filter(|query| !query.trim().is_empty())
+        .unwrap_or(INSIGHTS_DEFAULT_QUERY.to_owned());
+    match sources.stream_prefixes(apps) {
+        Some(prefixes) => {
+            let streams = prefixes
+                .iter()
+                .map(|(_, prefix)| regex::escape(prefix).replace('/', "\\/"))
+                .collect::<Vec<String>>()
+                .join("|");
+            format!("filter @logStream like /^({streams})/ | {query}")
+        }
+        None => query,
+    }
+}
+
+fn insights_row_to_log_entry(
+    row: &[cloudwatchlogs::types::ResultField],
+    sources: &LogSources,
+    fallback_timestamp: i64,
+) -> LogEntry {
+    let field = |name: &str| {
+        row.iter()
+            .find(|field| field.field() == Some(name))
+            .and_then(|field| field.value())
+    };
+    let parse_timestamp = |value: &str| {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.3f")
+            .ok()
+            .map(|value| value.and_utc().timestamp_millis())
+    };
+    // Aggregated rows have no @timestamp, a `bin(...)` column is the closest thing to one.
+    let timestamp = field("@timestamp")
+        .and_then(parse_timestamp)
+        .or_else(|| {
+            row.iter()
+                .filter_map(|field| field.value())
+                .find_map(parse_timestamp)
+        })
+        .unwrap_or(fallback_timestamp);
+    let message = match field("@message") {
+        Some(message) => message.to_owned(),
+        None => row
+            .iter()
+            .filter(|field| !field.field().unwrap_or_default().starts_with('@'))
+            .map(|field| {
+                format!(
+                    "{}={}",
+                    field.field().unwrap_or_default(),
+                    field.value().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" "),
+    };
+    let log_stream_name = field("@logStream").unwrap_or("-").to_owned();
+    let app = sources.app_of(&log_stream_name);
+    LogEntry::new(
+        log_stream_name,
+        app,
+        timestamp,
+        field("@ingestionTime")
+            .and_then(|value| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.3f").ok())
+            .map(|value| value.and_utc().timestamp_millis())
+            .unwrap_or(timestamp),
+        message,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn find_logs_insights(
+    config: &aws_config::SdkConfig,
+    env: Env,
+    apps: Vec<String>,
+    sources: &LogSources,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    query: Option<String>,
+    field_filter: Option<&FieldFilter>,
+    log_search_monitor: Arc<tokio::sync::Mutex<dyn LogSearchMonitor>>,
+    limit: Option<SearchLimit>,
+) -> Result<usize, CommandError> {
+    let client = cloudwatchlogs::Client::new(config);
+    let group_names = match sources.group_names(&client, &env, &apps).await {
+        Ok(group_names) => group_names,
+        Err(message) => {
+            log_search_monitor.lock().await.error(message.clone());
+            return Err(CommandError::new("find_logs", message));
+        }
+    };
+
+    if group_names.len() > INSIGHTS_MAX_LOG_GROUPS {
+        let message = format!(
+            "Insights queries support at most {INSIGHTS_MAX_LOG_GROUPS} log groups, {} matched",
+            group_names.len()
+        );
+        log_search_monitor.lock().await.error(message.clone());
+        return Err(CommandError::new("find_logs", message));
+    }
+
+    let query_string = insights_query(&apps, sources, query);
+    info!(
+        "Starting insights query in [{}]: {query_string}",
+        group_names.join(",")
+    );
+    {
+        let mut notifier = log_search_monitor.lock().await;
+        notifier.message(String::from("Starting insights query..."));
+    }
+    // Results come sorted by the query, keeping the last entries needs all of them.
+    let query_limit = limit
+        .filter(|limit| limit.policy != LimitPolicy::Last)
+        .map(|limit| i32::try_from(limit.max + 1).unwrap_or(INSIGHTS_MAX_LIMIT))
+        .unwrap_or(INSIGHTS_MAX_LIMIT)
+        .min(INSIGHTS_MAX_LIMIT);
+    let start_response = client
+        .start_query()
+        .set_log_group_names(Some(group_names))
+        .start_time(start_timestamp / 1000)
+        .end_time(end_timestamp / 1000)
+        .query_string(query_string)
+        .limit(query_limit)
+        .send()
+        .await;
+    let query_id = match start_response
+        .map_err(|err| err.into_service_error().to_string())
+        .and_then(|response| {
+            response
+                .query_id()
+                .map(|query_id| query_id.to_owned())
+                .ok_or(String::from("Missing query id"))
+        }) {
+        Ok(query_id) => query_id,
+        Err(message) => {
+            let message = format!("Error: {message}");
+            log_search_monitor.lock().await.error(message.clone());
+            return Err(CommandError::new("find_logs", message));
+        }
+    };
+    let mut guard = InsightsQueryGuard {
+        client: client.clone(),
+        query_id: query_id.clone(),
+        finished: false,
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    let mut log_count: usize = 0;
+    let mut tail = std::collections::VecDeque::new();
+    loop {
+        tokio::time::sleep(INSIGHTS_POLL_INTERVAL).await;
+        let response = match client.get_query_results().query_id(&query_id).send().await {
+            Ok(response) => response,
+            Err(err) => {
+                let message = format!("Error: {}", err.into_service_error());
+                log_search_monitor.lock().await.error(message.clone());
+                return Err(CommandError::new("find_logs", message));
+            }
+        };
+
+        let status = response
+            .status()
+            .cloned()
+            .unwrap_or(cloudwatchlogs::types::QueryStatus::Running);
+        let ptr = |row: &[cloudwatchlogs::types::ResultField]| {
+            row.iter()
+                .find(|field| field.field() == Some("@ptr"))
+                .and_then(|field| field.value())
+                .map(|ptr| ptr.to_owned())
+        };
+        let aggregated = response.results().iter().any(|row| ptr(row).is_none());
+        let mut new_entries: Vec<LogEntry> = if aggregated {
+            // Partial aggregates change with every poll, only the final result set is delivered.
+            match status {
+                cloudwatchlogs::types::QueryStatus::Complete => response
+                    .results()
+                    .iter()
+                    .map(|row| insights_row_to_log_entry(row, sources, end_timestamp))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        } else {
+            response
+                .results()
+                .iter()
+                .filter(|row| ptr(row).is_some_and(|ptr| seen.insert(ptr)))
+                .map(|row| insights_row_to_log_entry(row, sources, end_timestamp))
+                .collect()
+        };
+        if let Some(field_filter) = field_filter {
+            new_entries.retain(|log| field_filter.matches(log));
+        }
+        let limit_reached = limit.is_some_and(|limit| log_count + new_entries.len() > limit.max);
+        if let Some(SearchLimit {
+            max,
+            policy: LimitPolicy::First,
+        }) = limit
+        {
+            new_entries.truncate(max.saturating_sub(log_count));
+        }
+        log_count += new_entries.len();
+
+        let mut notifier = log_search_monitor.lock().await;
+        match limit {
+            Some(SearchLimit {
+                max,
+                policy: LimitPolicy::Last,
+            }) => keep_last(&mut tail, new_entries, max),
+            _ if !new_entries.is_empty() => notifier.notify(new_entries),
+            _ => {}
+        }
+        match limit {
+            Some(SearchLimit {
+                max,
+                policy: LimitPolicy::Abort,
+            }) if limit_reached => {
+                let msg = format!(
+                    "Insights query aborted, found {log_count} logs. Reached limit of {max} logs."
+                );
+                warn!("exceeded max log count, Limit {log_count}/{max}");
+                notifier.error(msg.clone());
+                return Err(CommandError::new("find_logs", msg));
+            }
+            Some(SearchLimit {
+                max,
+                policy: LimitPolicy::First,
+            }) if limit_reached => {
+                // Dropping the guard stops the query, its remaining results are not needed.
+                notifier.warning(SearchWarning {
+                    message: format!(
+                        "Showing the first {max} results, the query stopped at the limit."
+                    ),
+                    log_count,
+                    resume_from: None,
+                });
+                notifier.success(format!(
+                    "Insights query stopped at the limit of {max} results."
+                ));
+                return Ok(log_count);
+            }
+            _ => {}
+        }
+        match status {
+            cloudwatchlogs::types::QueryStatus::Complete => {
+                guard.finished = true;
+                if let Some(SearchLimit {
+                    max,
+                    policy: LimitPolicy::Last,
+                }) = limit
+                {
+                    let kept = tail.len();
+                    notifier.notify(std::mem::take(&mut tail).into());
+                    if log_count > max {
+                        notifier.warning(SearchWarning {
+                            message: format!("Showing the last {kept} of {log_count} results."),
+                            log_count,
+                            resume_from: None,
+                        });
+                    }
                 }
+                let scanned = response
+                    .statistics()
+                    .map(|statistics| statistics.records_scanned())
+                    .unwrap_or_default();
+                notifier.success(format!(
+                    "Insights query done. Found {log_count} results, scanned {scanned} records."
+                ));
+                return Ok(log_count);
+            }
+            cloudwatchlogs::types::QueryStatus::Scheduled
+            | cloudwatchlogs::types::QueryStatus::Running => {
                 notifier.message(format!(
-                    "Searching in {} log stream(s)...",
-                    stream_names.len()
+                    "Insights query {}, found {log_count} results...",
+                    status.as_str()
                 ));
             }
+            status => {
+                guard.finished = true;
+                let msg = format!("Insights query ended with status {}", status.as_str());
+                notifier.error(msg.clone());
+                return Err(CommandError::new("find_logs", msg));
+            }
+        }
+    }
+}
 
-            info!(
-                "found log streams: [{}]",
-                &stream_names.join(",").to_string()
-            );
+const TAIL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(2000);
+const TAIL_LOOKBACK_MILLIS: i64 = 30 * 1000;
+
+pub async fn tail_logs(
+    config: &aws_config::SdkConfig,
+    env: Env,
+    apps: Vec<String>,
+    sources: &LogSources,
+    filter: Option<String>,
+    log_search_monitor: Arc<tokio::sync::Mutex<dyn LogSearchMonitor>>,
+) -> Result<(), CommandError> {
+    let client = cloudwatchlogs::Client::new(config);
+    let group_names = match sources.group_names(&client, &env, &apps).await {
+        Ok(group_names) => group_names,
+        Err(message) => {
+            log_search_monitor.lock().await.error(message.clone());
+            return Err(CommandError::new("tail_logs", message));
+        }
+    };
+    {
+        let mut notifier = log_search_monitor.lock().await;
+        notifier.message(format!("Tailing logs of {}...", apps.join(", ")));
+    }
 
-            let mut marker = None;
-            let mut first = true;
-            for chunk in stream_names.chunks(100) {
-                while marker.as_ref().is_some() || first {
-                    first = false;
-                    let logs_response = client
+    let stream_prefixes: Vec<Option<String>> = match sources.stream_prefixes(&apps) {
+        Some(prefixes) => prefixes
+            .into_iter()
+            .map(|(_, prefix)| Some(prefix))
+            .collect(),
+        None => vec![None],
+    };
+
+    // Events can be ingested late, so every poll looks back and drops already seen event ids.
+    let mut cursor = Utc::now().timestamp_millis() - TAIL_LOOKBACK_MILLIS;
+    let mut seen: HashMap<String, i64> = HashMap::new();
+    let mut log_count: usize = 0;
+    loop {
+        // A quiet app moves no cursor, the window still has to follow the clock.
+        cursor = cursor.max(Utc::now().timestamp_millis() - TAIL_LOOKBACK_MILLIS);
+        let start_time = cursor - TAIL_LOOKBACK_MILLIS;
+        let mut new_entries = Vec::new();
+        for group_name in group_names.iter() {
+            for stream_prefix in stream_prefixes.iter() {
+                let mut marker = None;
+                loop {
+                    let request = client
                         .filter_log_events()
-                        .set_log_group_name(Some(group_name.to_owned()))
-                        .set_log_stream_names(Some(chunk.to_vec()))
-                        .set_next_token(marker)
+                        .log_group_name(group_name)
+                        .set_log_stream_name_prefix(stream_prefix.clone())
                         .set_filter_pattern(filter.clone())
-                        .set_start_time(Some(start_timestamp))
-                        .set_end_time(Some(end_timestamp))
-                        .send()
-                        .await;
-
-                    if let Err(err) = logs_response {
-                        let message = err
-                            .into_service_error()
-                            .meta()
-                            .message()
-                            .unwrap_or("")
-                            .to_owned();
-
-                        let mut notifier = log_search_monitor.lock().await;
-                        notifier.error(format!("Error: {}", &message).to_owned());
-
-                        return Result::Err(CommandError {
-                            message,
-                            command: "find_logs".to_owned(),
-                        });
+                        .start_time(start_time)
+                        .set_next_token(marker);
+                    let response =
+                        filter_log_events_with_backoff(group_name, || request.clone().send()).await;
+                    let response = match response {
+                        Ok(response) => response,
+                        Err(err) => {
+                            let message = err
+                                .into_service_error()
+                                .meta()
+                                .message()
+                                .unwrap_or("")
+                                .to_owned();
+                            warn!(
+                                "tail of {} in {group_name} failed, {message}",
+                                stream_prefix.as_deref().unwrap_or("all streams")
+                            );
+                            let message = format!("Tailing logs failed, {message}");
+                            log_search_monitor.lock().await.error(message.clone());
+                            return Err(CommandError::new("tail_logs", message));
+                        }
+                    };
+                    marker = response.next_token().map(|m| m.to_owned());
+                    for event in response.events.unwrap_or_default() {
+                        let timestamp = event.timestamp.unwrap_or_default();
+                        let event_id = event.event_id.unwrap_or_default();
+                        if seen.insert(event_id, timestamp).is_some() {
+                            continue;
+                        }
+                        let log_stream_name = event.log_stream_name.unwrap_or_default();
+                        let app = sources.app_of(&log_stream_name);
+                        new_entries.push(LogEntry::new(
+                            log_stream_name,
+                            app,
+                            timestamp,
+                            event.ingestion_time.unwrap_or_default(),
+                            event.message.unwrap_or_default(),
+                        ));
                     }
-                    let log_response_data = logs_response.unwrap();
-
-                    marker = log_response_data.next_token().map(|m| m.to_owned());
-                    let events = log_response_data.events.unwrap_or_default();
-                    info!("found {} logs", &events.len());
-                    log_count += events.len();
-                    let mut notifier = log_search_monitor.lock().await;
-                    notifier.notify(
-                        events
-                            .into_iter()
-                            .map(|event| LogEntry {
-                                log_stream_name: event.log_stream_name.unwrap_or_default(),
-                                timestamp: event.timestamp.unwrap_or_default(),
-                                ingestion_time: event.ingestion_time.unwrap_or_default(),
-                                message: event.message.unwrap_or_default().to_owned(),
-                            })
-                            .collect(),
-                    );
-                    notifier.message(format!(
-                        "Searching in {} log stream(s), found {log_count} logs...",
-                        stream_names.len()
-                    ));
-                    if let Some(limit) = limit {
-                        if log_count > limit {
-                            let msg = format!(
-                                "Search in {} log stream(s) aborted, found {} logs. Reached limit of {} logs.",
-                                stream_names.len(), &log_count, &limit
+                    if marker.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !new_entries.is_empty() {
+            new_entries.sort_by_key(|entry| entry.timestamp);
+            cursor = cursor.max(new_entries.last().map_or(cursor, |entry| entry.timestamp));
+            log_count += new_entries.len();
+            let mut notifier = log_search_monitor.lock().await;
+            notifier.notify(new_entries);
+            notifier.message(format!(
+                "Tailing logs of {}, received {log_count} logs...",
+                apps.join(", ")
+            ));
+        }
+        seen.retain(|_, timestamp| *timestamp >= cursor - 2 * TAIL_LOOKBACK_MILLIS);
+        tokio::time::sleep(TAIL_POLL_INTERVAL).await;
+    }
+}
+
+/// Buffers logs of one env until every other env delivered logs past them.
+struct EnvSearchCollector {
+    env: Env,
+    buffer: std::collections::VecDeque<LogEntry>,
+    /// Newest timestamp delivered, only advanced by searches delivering logs in order.
+    watermark: i64,
+    ordered: bool,
+    done: bool,
+    found: usize,
+    outcome: Option<Result<String, String>>,
+    truncated: bool,
+    progress: tokio::sync::mpsc::UnboundedSender<String>,
+    wake: Arc<tokio::sync::Notify>,
+}
+
+impl EnvSearchCollector {
+    fn new(
+        env: Env,
+        ordered: bool,
+        progress: tokio::sync::mpsc::UnboundedSender<String>,
+        wake: Arc<tokio::sync::Notify>,
+    ) -> EnvSearchCollector {
+        EnvSearchCollector {
+            env,
+            buffer: std::collections::VecDeque::new(),
+            watermark: i64::MIN,
+            ordered,
+            done: false,
+            found: 0,
+            outcome: None,
+            truncated: false,
+            progress,
+            wake,
+        }
+    }
+}
+
+impl LogSearchMonitor for EnvSearchCollector {
+    fn notify(&mut self, logs: Vec<LogEntry>) {
+        let env = self.env.clone();
+        self.found += logs.len();
+        if self.ordered {
+            self.watermark = logs
+                .iter()
+                .map(|log| log.timestamp)
+                .fold(self.watermark, i64::max);
+        }
+        self.buffer.extend(logs.into_iter().map(|mut log| {
+            log.env = Some(env.clone());
+            log
+        }));
+        self.buffer
+            .make_contiguous()
+            .sort_by_key(|log| log.timestamp);
+        self.wake.notify_one();
+    }
+    fn success(&mut self, msg: String) {
+        self.outcome = Some(Ok(msg));
+        self.done = true;
+        self.wake.notify_one();
+    }
+    fn error(&mut self, msg: String) {
+        self.outcome = Some(Err(msg));
+        self.done = true;
+        self.wake.notify_one();
+    }
+    fn message(&mut self, msg: String) {
+        let _ = self.progress.send(format!("[{}] {msg}", self.env));
+    }
+    fn warning(&mut self, warning: SearchWarning) {
+        self.truncated = true;
+        self.message(warning.message);
+    }
+}
+
+/// Takes the oldest buffered entries no unfinished env can deliver an older entry than.
+fn merge_envs<C: std::ops::DerefMut<Target = EnvSearchCollector>>(
+    collectors: &mut [C],
+) -> Vec<LogEntry> {
+    let mut batch = Vec::new();
+    loop {
+        let next = collectors
+            .iter()
+            .enumerate()
+            .filter_map(|(index, collector)| {
+                collector.buffer.front().map(|log| (log.timestamp, index))
+            })
+            .min();
+        let Some((timestamp, index)) = next else {
+            return batch;
+        };
+        let ready = collectors.iter().enumerate().all(|(other, collector)| {
+            other == index || collector.done || collector.watermark >= timestamp
+        });
+        if !ready {
+            return batch;
+        }
+        batch.extend(collectors[index].buffer.pop_front());
+    }
+}
+
+const MULTI_ENV_NOTIFY_CHUNK: usize = 500;
+/// Buffered entries of one env, past it the env search waits for the others to catch up.
+const MULTI_ENV_BUFFER_MAX: usize = 5000;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn find_logs_across_envs(
+    configs: Vec<(Env, aws_config::SdkConfig)>,
+    apps: Vec<String>,
+    sources: &LogSources,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    filter: Option<String>,
+    field_filter: Option<&FieldFilter>,
+    mode: LogSearchMode,
+    log_search_monitor: Arc<tokio::sync::Mutex<dyn LogSearchMonitor>>,
+    limit: Option<SearchLimit>,
+) -> Result<usize, CommandError> {
+    let (progress, mut progress_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let wake = Arc::new(tokio::sync::Notify::new());
+    let collectors: Vec<Arc<tokio::sync::Mutex<EnvSearchCollector>>> = configs
+        .iter()
+        .map(|(env, _)| {
+            Arc::new(tokio::sync::Mutex::new(EnvSearchCollector::new(
+                env.clone(),
+                mode == LogSearchMode::Filter,
+                progress.clone(),
+                wake.clone(),
+            )))
+        })
+        .collect();
+    drop(progress);
+
+    let mut log_count = 0;
+    let mut tail = std::collections::VecDeque::new();
+    let mut cut = false;
+    let mut aborted = false;
+    {
+        let searches = futures::future::join_all(configs.iter().zip(collectors.iter()).map(
+            |((env, config), collector)| {
+                let apps = apps.clone();
+                let filter = filter.clone();
+                let monitor: Arc<tokio::sync::Mutex<dyn LogSearchMonitor>> = collector.clone();
+                async move {
+                    match mode {
+                        LogSearchMode::Filter => {
+                            find_logs(
+                                config,
+                                env.clone(),
+                                apps,
+                                sources,
+                                start_timestamp,
+                                end_timestamp,
+                                filter,
+                                field_filter,
+                                monitor,
+                                limit,
+                                None,
                             )
-                            .to_owned();
-                            warn!("exceeded max log count, Limit {log_count}/{limit}");
-                            notifier.error(msg.to_owned());
-                            return Result::Err(CommandError {
-                                message: msg,
-                                command: "find_logs".to_owned(),
-                            });
+                            .await
+                        }
+                        LogSearchMode::Insights => {
+                            find_logs_insights(
+                                config,
+                                env.clone(),
+                                apps,
+                                sources,
+                                start_timestamp,
+                                end_timestamp,
+                                filter,
+                                field_filter,
+                                monitor,
+                                limit,
+                            )
+                            .await
                         }
                     }
                 }
+            },
+        ));
+        tokio::pin!(searches);
+        // Guards of envs with a full buffer, holding them pauses their search at the next notify.
+        let mut held: Vec<Option<tokio::sync::OwnedMutexGuard<EnvSearchCollector>>> =
+            collectors.iter().map(|_| None).collect();
+        let mut searching = true;
+        while searching {
+            tokio::select! {
+                _ = &mut searches => searching = false,
+                Some(msg) = progress_receiver.recv() => {
+                    log_search_monitor.lock().await.message(msg);
+                    continue;
+                }
+                _ = wake.notified() => {}
+            }
+            let mut guards = Vec::new();
+            for (collector, held) in collectors.iter().zip(held.iter_mut()) {
+                let guard = match (held.take(), searching) {
+                    (Some(guard), _) => guard,
+                    (None, false) => collector.clone().lock_owned().await,
+                    // An env busy with its monitor is merged after its next notification.
+                    (None, true) => match collector.clone().try_lock_owned() {
+                        Ok(guard) => guard,
+                        Err(_) => break,
+                    },
+                };
+                guards.push(guard);
+            }
+            if guards.len() < collectors.len() {
+                continue;
+            }
+            if !searching {
+                guards.iter_mut().for_each(|guard| guard.done = true);
+            }
+            let mut logs = merge_envs(&mut guards);
+            // Each env is limited on its own, the merged result is held to the same limit.
+            match limit {
+                Some(SearchLimit {
+                    max,
+                    policy: LimitPolicy::First,
+                }) if log_count + logs.len() > max => {
+                    logs.truncate(max - log_count);
+                    cut = true;
+                }
+                Some(SearchLimit {
+                    max,
+                    policy: LimitPolicy::Abort,
+                }) if log_count + logs.len() > max => aborted = true,
+                _ => {}
+            }
+            if !logs.is_empty() {
+                log_count += logs.len();
+                match limit {
+                    Some(SearchLimit {
+                        max,
+                        policy: LimitPolicy::Last,
+                    }) => keep_last(&mut tail, logs, max),
+                    _ => log_search_monitor.lock().await.notify(logs),
+                }
+            }
+            // Dropping the searches stops the env searches still running.
+            if cut || aborted {
+                break;
+            }
+            for (guard, held) in guards.into_iter().zip(held.iter_mut()) {
+                // Unordered envs never release entries before finishing, they are not paused.
+                if guard.ordered && guard.buffer.len() >= MULTI_ENV_BUFFER_MAX {
+                    *held = Some(guard);
+                }
             }
         }
     }
-    info!("logs search finished");
+    while let Ok(msg) = progress_receiver.try_recv() {
+        log_search_monitor.lock().await.message(msg);
+    }
+    if let Some(SearchLimit { max, .. }) = limit.filter(|_| aborted) {
+        let message = format!(
+            "Search in {} env(s) aborted, found {log_count} logs. Reached limit of {max} logs.",
+            configs.len()
+        );
+        warn!("exceeded max log count across envs, Limit {log_count}/{max}");
+        log_search_monitor.lock().await.error(message.clone());
+        return Err(CommandError::new("find_logs_across_envs", message));
+    }
+
+    let mut found = 0;
+    let mut failures = Vec::new();
+    let mut truncated = cut;
+    for collector in collectors {
+        let mut collector = collector.lock().await;
+        truncated |= collector.truncated;
+        found += collector.found;
+        if let Some(Err(msg)) = collector.outcome.take() {
+            failures.push(format!("{}: {msg}", collector.env));
+        }
+    }
+    let log_count = match limit {
+        Some(SearchLimit {
+            policy: LimitPolicy::Last,
+            ..
+        }) => tail.len(),
+        _ => log_count,
+    };
+    info!(
+        "multi env search finished, envs={}, logs={log_count}",
+        configs.len()
+    );
 
     let mut notifier = log_search_monitor.lock().await;
+    let mut tail = tail.into_iter().peekable();
+    while tail.peek().is_some() {
+        notifier.notify(tail.by_ref().take(MULTI_ENV_NOTIFY_CHUNK).collect());
+    }
+    if truncated || log_count < found {
+        notifier.warning(SearchWarning {
+            message: format!(
+                "Showing {log_count} of {found} logs found in {} env(s), results were truncated at the limit.",
+                configs.len()
+            ),
+            log_count: found,
+            resume_from: None,
+        });
+    }
+    if !failures.is_empty() {
+        let message = format!(
+            "Search failed in {} of {} env(s), found {log_count} logs. {}",
+            failures.len(),
+            configs.len(),
+            failures.join("; ")
+        );
+        notifier.error(message.clone());
+        return Err(CommandError::new("find_logs_across_envs", message));
+    }
     notifier.success(format!(
-        "Search in {} log stream(s) done. Found {log_count} logs.",
-        stream_names.len()
+        "Search in {} env(s) done. Found {log_count} logs.",
+        configs.len()
     ));
-    Result::Ok(log_count)
+    Ok(log_count)
 }
 
 async fn find_stream_names(
     client: &cloudwatchlogs::Client,
     group_name: &str,
     apps: &[String],
+    sources: &LogSources,
     start_timestamp: i64,
     end_timestamp: i64,
     log_search_monitor: Arc<tokio::sync::Mutex<dyn LogSearchMonitor>>,
@@ -1636,9 +2835,7 @@ async fn find_stream_names(
         for stream in streams {
             let stream_name = stream.log_stream_name.unwrap_or_default();
 
-            let app = apps
-                .iter()
-                .find(|app| stream_name.starts_with(&format!("web/{}/", &app)));
+            let app = sources.app_for_stream(&stream_name, apps);
             if let Some(app) = app {
                 let last_known_creation_time: i64 =
                     last_creation_dates.get(app).copied().unwrap_or(i64::MAX);
@@ -1723,3 +2920,210 @@ pub fn cli_login(profile: &str) {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(timestamp: i64) -> LogEntry {
+        LogEntry::new(
+            "web/app/1".to_owned(),
+            Some("app".to_owned()),
+            timestamp,
+            timestamp,
+            format!("at {timestamp}"),
+        )
+    }
+
+    fn feed(pages: Vec<Vec<i64>>) -> ChunkFeed {
+        let (sender, receiver) = tokio::sync::mpsc::channel(pages.len() + 1);
+        for page in pages {
+            sender
+                .try_send(Ok(page.into_iter().map(log).collect()))
+                .unwrap();
+        }
+        ChunkFeed::new(receiver)
+    }
+
+    async fn merge_all(mut feeds: Vec<ChunkFeed>) -> Vec<i64> {
+        let mut merged = Vec::new();
+        loop {
+            for feed in feeds.iter_mut() {
+                feed.fill().await.unwrap();
+            }
+            let batch = merge_ready(&mut feeds);
+            if batch.is_empty() {
+                return merged;
+            }
+            merged.extend(batch.iter().map(|log| log.timestamp));
+        }
+    }
+
+    fn timestamps(logs: &[LogEntry]) -> Vec<i64> {
+        logs.iter().map(|log| log.timestamp).collect()
+    }
+
+    #[tokio::test]
+    async fn merges_feeds_in_timestamp_order() {
+        let merged = merge_all(vec![
+            feed(vec![vec![1, 4], vec![6, 9]]),
+            feed(vec![vec![2, 3], vec![5, 10]]),
+            feed(vec![]),
+        ])
+        .await;
+        assert_eq!(merged, vec![1, 2, 3, 4, 5, 6, 9, 10]);
+    }
+
+    #[tokio::test]
+    async fn holds_entries_until_a_later_page_starts() {
+        let merged = merge_all(vec![feed(vec![vec![5, 8], vec![6, 7], vec![], vec![9]])]).await;
+        assert_eq!(merged, vec![5, 6, 7, 8, 9]);
+    }
+
+    #[tokio::test]
+    async fn reports_page_errors() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        sender.try_send(Err("boom".to_owned())).unwrap();
+        let mut feed = ChunkFeed::new(receiver);
+        assert_eq!(feed.fill().await, Err("boom".to_owned()));
+    }
+
+    #[test]
+    fn only_searches_keeping_raw_entries_are_limited() {
+        let first = SearchLimit {
+            max: 50,
+            policy: LimitPolicy::First,
+        };
+        assert!(matches!(
+            SearchLimit::for_search(None, false, false),
+            Some(SearchLimit {
+                max: DEFAULT_SEARCH_LIMIT,
+                policy: LimitPolicy::Abort
+            })
+        ));
+        assert!(matches!(
+            SearchLimit::for_search(Some(first), false, false),
+            Some(SearchLimit {
+                max: 50,
+                policy: LimitPolicy::First
+            })
+        ));
+        assert!(SearchLimit::for_search(Some(first), true, false).is_none());
+        assert!(SearchLimit::for_search(Some(first), false, true).is_none());
+    }
+
+    #[test]
+    fn resume_skip_drops_delivered_entries_across_batches() {
+        let mut skip = ResumeSkip::new(&SearchProgress {
+            last_timestamp: Some(5),
+            entries_at_last_timestamp: 2,
+            log_count: 10,
+        });
+        let mut first = vec![log(5)];
+        skip.apply(&mut first);
+        assert!(first.is_empty());
+
+        let mut second = vec![log(5), log(5), log(7)];
+        skip.apply(&mut second);
+        assert_eq!(timestamps(&second), vec![5, 7]);
+
+        let mut third = vec![log(7)];
+        skip.apply(&mut third);
+        assert_eq!(timestamps(&third), vec![7]);
+    }
+
+    #[test]
+    fn resume_skip_stops_once_past_checkpoint_timestamp() {
+        let mut skip = ResumeSkip::new(&SearchProgress {
+            last_timestamp: Some(5),
+            entries_at_last_timestamp: 3,
+            log_count: 3,
+        });
+        let mut first = vec![log(5), log(6)];
+        skip.apply(&mut first);
+        assert_eq!(timestamps(&first), vec![6]);
+
+        let mut second = vec![log(6), log(6)];
+        skip.apply(&mut second);
+        assert_eq!(timestamps(&second), vec![6, 6]);
+    }
+
+    #[test]
+    fn resume_skip_ignores_entries_dropped_by_field_filter() {
+        let field_filter = FieldFilter::parse("message=at").unwrap();
+        let mut skip = ResumeSkip::new(&SearchProgress {
+            last_timestamp: Some(5),
+            entries_at_last_timestamp: 1,
+            log_count: 1,
+        });
+        let mut dropped = log(5);
+        dropped.message = String::from("other");
+        let mut logs = vec![dropped, log(5), log(6)];
+        skip.apply_matching(&mut logs, Some(&field_filter));
+        assert_eq!(timestamps(&logs), vec![6]);
+    }
+
+    #[test]
+    fn resume_skip_without_checkpoint_keeps_everything() {
+        let mut skip = ResumeSkip::new(&SearchProgress::default());
+        let mut logs = vec![log(1), log(1)];
+        skip.apply(&mut logs);
+        assert_eq!(timestamps(&logs), vec![1, 1]);
+    }
+
+    #[test]
+    fn advance_progress_counts_entries_at_last_timestamp() {
+        let mut progress = SearchProgress::default();
+        advance_progress(&mut progress, &[log(1), log(2), log(2)]);
+        assert_eq!(progress.last_timestamp, Some(2));
+        assert_eq!(progress.entries_at_last_timestamp, 2);
+        advance_progress(&mut progress, &[log(2)]);
+        assert_eq!(progress.entries_at_last_timestamp, 3);
+        advance_progress(&mut progress, &[log(4)]);
+        assert_eq!(progress.entries_at_last_timestamp, 1);
+        assert_eq!(progress.log_count, 5);
+    }
+
+    fn collector(env: Env, ordered: bool) -> EnvSearchCollector {
+        let (progress, _) = tokio::sync::mpsc::unbounded_channel();
+        EnvSearchCollector::new(env, ordered, progress, Arc::new(tokio::sync::Notify::new()))
+    }
+
+    #[test]
+    fn merges_envs_up_to_the_slowest_env() {
+        let mut dev = collector(Env::DEV, true);
+        let mut prod = collector(Env::PROD, true);
+        dev.notify(vec![log(1), log(4), log(8)]);
+        prod.notify(vec![log(2), log(5)]);
+        assert_eq!(
+            timestamps(&merge_envs(&mut [&mut dev, &mut prod])),
+            vec![1, 2, 4, 5]
+        );
+
+        prod.notify(vec![log(9)]);
+        assert_eq!(timestamps(&merge_envs(&mut [&mut dev, &mut prod])), vec![8]);
+
+        dev.success(String::new());
+        let merged = merge_envs(&mut [&mut dev, &mut prod]);
+        assert_eq!(timestamps(&merged), vec![9]);
+        assert_eq!(merged[0].env, Some(Env::PROD));
+    }
+
+    #[test]
+    fn holds_unordered_envs_until_they_finish() {
+        let mut dev = collector(Env::DEV, false);
+        let mut prod = collector(Env::PROD, false);
+        dev.notify(vec![log(3), log(1)]);
+        prod.notify(vec![log(2)]);
+        assert!(merge_envs(&mut [&mut dev, &mut prod]).is_empty());
+
+        prod.error(String::from("boom"));
+        assert_eq!(timestamps(&merge_envs(&mut [&mut dev, &mut prod])), vec![1]);
+
+        dev.success(String::new());
+        assert_eq!(
+            timestamps(&merge_envs(&mut [&mut dev, &mut prod])),
+            vec![2, 3]
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use crate::shared::{CommandError, Env};
+use crate::{
+    aws, log_dump, log_summary, wombat_api, AppContextState, AsyncTaskManager,
+    AwsConfigProviderInstance, EcsResolverInstance, LogSearchRequest, RdsResolverInstance,
+    UserConfigState, WombatApiInstance,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use tauri::{AppHandle, Manager};
+use warp::filters::BoxedFilter;
+use warp::http::StatusCode;
+use warp::reply::Reply;
+use warp::Filter;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Job {
+    arn: String,
+    port: Option<u16>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Port {
+    arn: String,
+    port: u16,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartProxyBody {
+    app: String,
+    env: Env,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    proxy_auth_config_ids: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FindLogsBody {
+    apps: Vec<String>,
+    env: Env,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    filter: Option<String>,
+    filename: String,
+    mode: Option<aws::LogSearchMode>,
+    field_filter: Option<String>,
+    dump: Option<log_dump::DumpOptions>,
+    log_groups: Option<Vec<String>>,
+    summary: Option<log_summary::SummaryOptions>,
+}
+
+fn reply_with<T: Serialize>(result: Result<T, CommandError>) -> warp::reply::Response {
+    match result {
+        Ok(value) => warp::reply::json(&value).into_response(),
+        Err(e) => {
+            log::warn!("Control api {} failed, {}", &e.command, &e.message);
+            warp::reply::with_status(warp::reply::json(&e), StatusCode::BAD_REQUEST).into_response()
+        }
+    }
+}
+
+fn not_found(command: &str, message: String) -> warp::reply::Response {
+    log::warn!("Control api {command} failed, {message}");
+    warp::reply::with_status(
+        warp::reply::json(&CommandError::new(command, message)),
+        StatusCode::NOT_FOUND,
+    )
+    .into_response()
+}
+
+fn parse_env(env: &str, command: &str) -> Result<Env, CommandError> {
+    match Env::from_exact(&env.to_lowercase()) {
+        Env::DEVNULL => Err(CommandError::new(command, format!("Unknown env {env}"))),
+        env => Ok(env),
+    }
+}
+
+async fn ensure_logged_in(app_handle: &AppHandle, command: &str) -> Result<(), CommandError> {
+    let app_state = app_handle.state::<AppContextState>();
+    let app_ctx = app_state.0.lock().await;
+    match app_ctx.active_profile {
+        Some(_) => Ok(()),
+        None => Err(CommandError::new(command, "Not logged in")),
+    }
+}
+
+async fn find_db(
+    app_handle: &AppHandle,
+    app: &str,
+    env: &Env,
+    command: &str,
+) -> Result<aws::RdsInstance, CommandError> {
+    let rds_resolver = app_handle.state::<RdsResolverInstance>();
+    let rds_resolver = rds_resolver.0.read().await;
+    rds_resolver
+        .read_databases()
+        .await
+        .into_iter()
+        .find(|db| db.appname_tag == app && &db.env == env)
+        .ok_or_else(|| CommandError::new(command, format!("No database for {app} on {env}")))
+}
+
+async fn find_service(
+    app_handle: &AppHandle,
+    app: &str,
+    env: &Env,
+    command: &str,
+) -> Result<aws::EcsService, CommandError> {
+    let ecs_resolver = app_handle.state::<EcsResolverInstance>();
+    let ecs_resolver = ecs_resolver.0.read().await;
+    ecs_resolver
+        .read_services()
+        .await
+        .into_iter()
+        .find(|service| service.name == app && &service.env == env)
+        .ok_or_else(|| CommandError::new(command, format!("No service for {app} on {env}")))
+}
+
+async fn find_arn(
+    app_handle: &AppHandle,
+    kind: &str,
+    app: &str,
+    env: &Env,
+    command: &str,
+) -> Result<String, CommandError> {
+    match kind {
+        "db" => Ok(find_db(app_handle, app, env, command).await?.arn),
+        "service" => Ok(find_service(app_handle, app, env, command).await?.arn),
+        _ => Err(CommandError::new(
+            command,
+            format!("Unknown proxy kind {kind}, expected db or service"),
+        )),
+    }
+}
+
+async fn jobs(app_handle: AppHandle) -> Result<warp::reply::Response, warp::Rejection> {
+    let arns: BTreeSet<String>;
+    {
+        let tracker = app_handle.state::<AsyncTaskManager>();
+        let tracker = tracker.0.lock().await;
+        arns = tracker
+            .proxies_handlers
+            .keys()
+            .chain(tracker.task_handlers.keys())
+            .cloned()
+            .collect();
+    }
+    let user_config = app_handle.state::<UserConfigState>();
+    let user_config = user_config.0.lock().await;
+    let jobs: Vec<Job> = arns
+        .into_iter()
+        .map(|arn| Job {
+            port: user_config.assigned_port(&arn),
+            arn,
+        })
+        .collect();
+    Ok(reply_with(Ok(jobs)))
+}
+
+async fn start_db_proxy(
+    body: StartProxyBody,
+    app_handle: AppHandle,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let result = async {
+        ensure_logged_in(&app_handle, "start_db_proxy").await?;
+        let db = find_db(&app_handle, &body.app, &body.env, "start_db_proxy").await?;
+        crate::start_db_proxy(
+            app_handle.clone(),
+            db,
+            app_handle.state(),
+            app_handle.state(),
+            app_handle.state(),
+            app_handle.state(),
+            app_handle.state(),
+        )
+        .await
+    }
+    .await;
+    Ok(reply_with(result))
+}
+
+async fn start_service_proxy(
+    body: StartProxyBody,
+    app_handle: AppHandle,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let result = async {
+        ensure_logged_in(&app_handle, "start_service_proxy").await?;
+        let service =
+            find_service(&app_handle, &body.app, &body.env, "start_service_proxy").await?;
+        let (infra_profile, sso_profile) = {
+            let aws_config_provider = app_handle.state::<AwsConfigProviderInstance>();
+            let aws_config_provider = aws_config_provider.0.read().await;
+            aws_config_provider
+                .default_profiles(&service.name, &service.env)
+                .map_err(|msg| CommandError::new("start_service_proxy", msg))?
+        };
+        let proxy_auth_configs: Vec<wombat_api::ProxyAuthConfig> =
+            if body.proxy_auth_config_ids.is_empty() {
+                Vec::new()
+            } else {
+                let wombat_api = app_handle.state::<WombatApiInstance>();
+                let mut wombat_api = wombat_api.0.lock().await;
+                let configs: Vec<wombat_api::ProxyAuthConfig> = wombat_api
+                    .get_proxy_auth_configs()
+                    .await?
+                    .configs
+                    .into_iter()
+                    .filter(|config| body.proxy_auth_config_ids.contains(&config.id))
+                    .collect();
+                let unknown_ids: Vec<String> = body
+                    .proxy_auth_config_ids
+                    .iter()
+                    .filter(|id| !configs.iter().any(|config| &config.id == *id))
+                    .map(|id| id.to_string())
+                    .collect();
+                if !unknown_ids.is_empty() {
+                    return Err(CommandError::new(
+                        "start_service_proxy",
+                        format!("Unknown proxy auth config ids: {}", unknown_ids.join(", ")),
+                    ));
+                }
+                configs
+            };
+        crate::start_service_proxy(
+            app_handle.clone(),
+            service,
+            Some(infra_profile),
+            Some(sso_profile),
+            body.headers,
+            proxy_auth_configs,
+            app_handle.state(),
+            app_handle.state(),
+            app_handle.state(),
+            app_handle.state(),
+            app_handle.state(),
+        )
+        .await
+    }
+    .await;
+    Ok(reply_with(result))
+}
+
+async fn stop_proxy(
+    kind: String,
+    app: String,
+    env: String,
+    app_handle: AppHandle,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let result = async {
+        ensure_logged_in(&app_handle, "stop_job").await?;
+        let env = parse_env(&env, "stop_job")?;
+        let arn = find_arn(&app_handle, &kind, &app, &env, "stop_job").await?;
+        crate::stop_job(
+            app_handle.clone(),
+            &arn,
+            app_handle.state(),
+            app_handle.state(),
+        )
+        .await
+    }
+    .await;
+    Ok(reply_with(result))
+}
+
+async fn proxy_port(
+    kind: String,
+    app: String,
+    env: String,
+    app_handle: AppHandle,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let result = async {
+        ensure_logged_in(&app_handle, "proxy_port").await?;
+        let env = parse_env(&env, "proxy_port")?;
+        let arn = find_arn(&app_handle, &kind, &app, &env, "proxy_port").await?;
+        let user_config = app_handle.state::<UserConfigState>();
+        let user_config = user_config.0.lock().await;
+        Ok((user_config.assigned_port(&arn), arn))
+    }
+    .await;
+    // Ports are only assigned by starting a proxy, reading one must not allocate it.
+    Ok(match result {
+        Ok((Some(port), arn)) => reply_with(Ok(Port { arn, port })),
+        Ok((None, arn)) => not_found("proxy_port", format!("No port assigned to {arn}")),
+        Err(e) => reply_with::<Port>(Err(e)),
+    })
+}
+
+async fn find_logs(
+    body: FindLogsBody,
+    app_handle: AppHandle,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let result = async {
+        ensure_logged_in(&app_handle, "find_logs").await?;
+        crate::start_log_search(
+            &app_handle,
+            LogSearchRequest {
+                apps: body.apps,
+                env: body.env,
+                start_timestamp: body.start_timestamp,
+                end_timestamp: body.end_timestamp,
+                filter: body.filter,
+                filename: Some(body.filename),
+                mode: body.mode,
+                field_filter: body.field_filter,
+                dump: body.dump,
+                log_groups: body.log_groups,
+                summary: body.summary,
+                search_id: None,
+                limit: None,
+                resume_from: None,
+            },
+        )
+        .await
+    }
+    .await;
+    Ok(match result {
+        Ok(search_id) => {
+            warp::reply::with_status(warp::reply::json(&search_id), StatusCode::ACCEPTED)
+                .into_response()
+        }
+        Err(e) => reply_with::<()>(Err(e)),
+    })
+}
+
+async fn deployment_status(
+    app: String,
+    env: String,
+    app_handle: AppHandle,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let result = async {
+        ensure_logged_in(&app_handle, "deployment_status").await?;
+        let env = parse_env(&env, "deployment_status")?;
+        let service = find_service(&app_handle, &app, &env, "deployment_status").await?;
+        let ecs_resolver = app_handle.state::<EcsResolverInstance>();
+        let ecs_resolver = ecs_resolver.0.read().await;
+        ecs_resolver
+            .deployment_status(&service.arn)
+            .await
+            .ok_or_else(|| {
+                CommandError::new(
+                    "deployment_status",
+                    format!("No deployment tracked for {app} on {env}"),
+                )
+            })
+    }
+    .await;
+    Ok(reply_with(result))
+}
+
+fn with_app_handle(
+    app_handle: AppHandle,
+) -> impl Filter<Extract = (AppHandle,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || app_handle.clone())
+}
+
+pub fn routes(app_handle: AppHandle) -> BoxedFilter<(warp::reply::Response,)> {
+    warp::get()
+        .and(warp::path!("jobs"))
+        .and(with_app_handle(app_handle.clone()))
+        .and_then(jobs)
+        .or(warp::post()
+            .and(warp::path!("proxies" / "db"))
+            .and(warp::body::json())
+            .and(with_app_handle(app_handle.clone()))
+            .and_then(start_db_proxy))
+        .unify()
+        .or(warp::post()
+            .and(warp::path!("proxies" / "service"))
+            .and(warp::body::json())
+            .and(with_app_handle(app_handle.clone()))
+            .and_then(start_service_proxy))
+        .unify()
+        .or(warp::delete()
+            .and(warp::path!("proxies" / String / String / String))
+            .and(with_app_handle(app_handle.clone()))
+            .and_then(stop_proxy))
+        .unify()
+        .or(warp::get()
+            .and(warp::path!("proxies" / String / String / String / "port"))
+            .and(with_app_handle(app_handle.clone()))
+            .and_then(proxy_port))
+        .unify()
+        .or(warp::post()
+            .and(warp::path!("logs"))
+            .and(warp::body::json())
+            .and(with_app_handle(app_handle.clone()))
+            .and_then(find_logs))
+        .unify()
+        .or(warp::get()
+            .and(warp::path!("deployments" / String / String))
+            .and(with_app_handle(app_handle))
+            .and_then(deployment_status))
+        .unify()
+        .boxed()
+}
@@ -6,6 +6,7 @@ pub const CODEARTIFACT_LOGIN: &str = "codeartifact-login";
 pub const AWS_CLI: &str = "aws-cli";
 pub const SESSION_MANAGER_PLUGIN: &str = "session-manager-plugin";
 pub const WOMBAT_API: &str = "wombat-backend-api";
+pub const REST_API: &str = "rest-api";
 
 #[derive(Clone, serde::Serialize)]
 pub struct Dependency {
@@ -18,6 +19,7 @@ pub struct Dependency {
 pub async fn check_dependencies(
     wombat_api: &mut WombatApi,
     aws_config_provider: &aws::AwsConfigProvider,
+    rest_api_status: &Result<u16, String>,
     required_feature: &str,
 ) -> Vec<Dependency> {
     let mut dependecies = Vec::new();
@@ -126,6 +128,21 @@ pub async fn check_dependencies(
         },
     });
 
+    dependecies.push(match rest_api_status {
+        Ok(port) => Dependency {
+            name: REST_API.to_string(),
+            ok: true,
+            required: false,
+            version_or_error: format!("Listening on 127.0.0.1:{port}"),
+        },
+        Err(e) => Dependency {
+            name: REST_API.to_string(),
+            ok: false,
+            required: false,
+            version_or_error: format!("{e}. Change the port in config and restart."),
+        },
+    });
+
     let is_codeartifact_login_in_path = is_program_in_path(CODEARTIFACT_LOGIN);
     dependecies.push(Dependency {
         name: CODEARTIFACT_LOGIN.to_string(),
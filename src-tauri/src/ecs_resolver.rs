@@ -14,6 +14,7 @@ const CACHE_NAME: &str = "ecs";
 pub struct EcsResolver {
     db_pool: Arc<Pool<SqliteConnectionManager>>,
     aws_config_resolver: Arc<RwLock<aws::AwsConfigProvider>>,
+    deployments: Arc<RwLock<HashMap<String, DeplyomentStatus>>>,
 }
 
 impl EcsResolver {
@@ -24,6 +25,7 @@ impl EcsResolver {
         EcsResolver {
             db_pool,
             aws_config_resolver,
+            deployments: Arc::new(RwLock::new(HashMap::new())),
         }
     }
     pub fn init(&mut self, db_pool: Arc<Pool<SqliteConnectionManager>>) {
@@ -94,6 +96,7 @@ impl EcsResolver {
             let deployment_id = deployment_res_clone.unwrap().clone();
             let mut error_count = 0;
             let fail_deploy_after = chrono::Utc::now() + chrono::Duration::minutes(15);
+            let deployments = self.deployments.clone();
 
             tokio::task::spawn(async move {
                 let mut interval = tokio::time::interval(std::time::Duration::from_millis(5000));
@@ -141,17 +144,19 @@ impl EcsResolver {
                         error_message = Some("Timed out after 15m. Check in AWS console".to_owned())
                     }
 
-                    let _ = app_handle.emit(
-                        "deployment",
-                        DeplyomentStatus {
-                            deployment_id: deployment_id.to_owned(),
-                            cluster_arn: cluster_arn.clone(),
-                            service_name: service_name.clone(),
-                            rollout_status: status_str.to_owned(),
-                            version: desired_version.clone(),
-                            error_message,
-                        },
-                    );
+                    let status = DeplyomentStatus {
+                        deployment_id: deployment_id.to_owned(),
+                        cluster_arn: cluster_arn.clone(),
+                        service_name: service_name.clone(),
+                        rollout_status: status_str.to_owned(),
+                        version: desired_version.clone(),
+                        error_message,
+                    };
+                    deployments
+                        .write()
+                        .await
+                        .insert(service_arn.clone(), status.clone());
+                    let _ = app_handle.emit("deployment", status);
                     continue_checking = status_str == "In Progress" || status_str == "Unknown";
                 }
             });
@@ -160,6 +165,10 @@ impl EcsResolver {
         deplyoment_res
     }
 
+    pub async fn deployment_status(&self, service_arn: &str) -> Option<DeplyomentStatus> {
+        self.deployments.read().await.get(service_arn).cloned()
+    }
+
     pub async fn services(&mut self, clusters: Vec<aws::Cluster>) -> Vec<aws::EcsService> {
         info!("Resolving services for clusters {clusters:?}");
         let aws_config_resolver = self.aws_config_resolver.read().await;
@@ -287,7 +296,7 @@ fn store_services(pool: &Pool<SqliteConnectionManager>, services: &[aws::EcsServ
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-struct DeplyomentStatus {
+pub struct DeplyomentStatus {
     deployment_id: String,
     service_name: String,
     cluster_arn: String,
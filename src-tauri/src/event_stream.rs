@@ -0,0 +1,153 @@
+use crate::ecs_resolver::DeplyomentStatus;
+use crate::shared::{CookieJar, CookieJarStatus};
+use crate::{ProxyStarted, ServiceDetailsPayload, TaskKilled};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Listener};
+use tokio::sync::{broadcast, Mutex};
+
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+const EVENT_BUFFER_SIZE: usize = 256;
+const COOKIE_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+pub const FORWARDED_EVENTS: [&str; 6] = [
+    "task-killed",
+    "deployment",
+    "cache-refreshed",
+    "new-service-details",
+    "proxy-started",
+    "cookie-health-changed",
+];
+
+/// App events forwarded to rest api clients, tagged with their event name.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", content = "payload", rename_all = "kebab-case")]
+pub enum ForwardedEvent {
+    TaskKilled(TaskKilled),
+    Deployment(DeplyomentStatus),
+    CacheRefreshed,
+    NewServiceDetails(ServiceDetailsPayload),
+    ProxyStarted(ProxyStarted),
+    CookieHealthChanged(CookieJarStatus),
+}
+
+impl ForwardedEvent {
+    fn parse(event_type: &str, payload: &str) -> serde_json::Result<ForwardedEvent> {
+        let payload: serde_json::Value = serde_json::from_str(payload)?;
+        serde_json::from_value(serde_json::json!({ "type": event_type, "payload": payload }))
+    }
+
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            ForwardedEvent::TaskKilled(_) => "task-killed",
+            ForwardedEvent::Deployment(_) => "deployment",
+            ForwardedEvent::CacheRefreshed => "cache-refreshed",
+            ForwardedEvent::NewServiceDetails(_) => "new-service-details",
+            ForwardedEvent::ProxyStarted(_) => "proxy-started",
+            ForwardedEvent::CookieHealthChanged(_) => "cookie-health-changed",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EventEnvelope {
+    pub version: u32,
+    pub emitted_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: ForwardedEvent,
+}
+
+#[derive(Clone)]
+pub struct EventStream {
+    sender: broadcast::Sender<EventEnvelope>,
+}
+
+impl Default for EventStream {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        EventStream { sender }
+    }
+}
+
+impl EventStream {
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+
+    pub fn forward_app_events(&self, app_handle: &AppHandle) {
+        for event_type in FORWARDED_EVENTS {
+            let sender = self.sender.clone();
+            app_handle.listen_any(event_type, move |event| {
+                let event = match ForwardedEvent::parse(event_type, event.payload()) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!("Failed to parse {event_type} payload, {e}");
+                        return;
+                    }
+                };
+                // Sending fails only when nobody is subscribed.
+                let _ = sender.send(EventEnvelope {
+                    version: EVENT_SCHEMA_VERSION,
+                    emitted_at: Utc::now(),
+                    event,
+                });
+            });
+        }
+    }
+}
+
+pub fn watch_cookie_health(app_handle: AppHandle, jar: Arc<Mutex<CookieJar>>) {
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(COOKIE_HEALTH_CHECK_INTERVAL);
+        let mut last_status: Option<CookieJarStatus> = None;
+        loop {
+            interval.tick().await;
+            let status = jar.lock().await.to_status();
+            if last_status.as_ref() != Some(&status) {
+                info!("Cookie health changed: {:?}", &status.cookie_health);
+                let _ = app_handle.emit("cookie-health-changed", status.clone());
+                last_status = Some(status);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope_json(event_type: &str, payload: &str) -> serde_json::Value {
+        let envelope = EventEnvelope {
+            version: EVENT_SCHEMA_VERSION,
+            emitted_at: Utc::now(),
+            event: ForwardedEvent::parse(event_type, payload).unwrap(),
+        };
+        serde_json::to_value(envelope).unwrap()
+    }
+
+    #[test]
+    fn serializes_events_tagged_by_type() {
+        let json = envelope_json("proxy-started", r#"{"arn": "arn:db", "port": 5432}"#);
+        assert_eq!(json["version"], EVENT_SCHEMA_VERSION);
+        assert_eq!(json["type"], "proxy-started");
+        assert_eq!(json["payload"]["arn"], "arn:db");
+        assert_eq!(json["payload"]["port"], 5432);
+        assert!(json["emittedAt"].is_string());
+
+        let json = envelope_json("cache-refreshed", "null");
+        assert_eq!(json["type"], "cache-refreshed");
+        assert!(json["payload"].is_null());
+    }
+
+    #[test]
+    fn rejects_unknown_events_and_mismatched_payloads() {
+        let event = ForwardedEvent::parse("task-killed", r#"{"arn": "arn:task"}"#).unwrap();
+        assert_eq!(event.event_type(), "task-killed");
+        assert!(ForwardedEvent::parse("proxy-started", r#"{"arn": "arn:db"}"#).is_err());
+        assert!(ForwardedEvent::parse("unknown", "null").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::log_dump::DumpOptions;
+use crate::shared::{CommandError, Env};
+use crate::user::wombat_dir;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Position of a filter search in its timestamp ordered output.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchProgress {
+    pub last_timestamp: Option<i64>,
+    /// Entries already delivered at `last_timestamp`, skipped when the search resumes.
+    pub entries_at_last_timestamp: usize,
+    pub log_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpState {
+    pub base_path: PathBuf,
+    pub files: Vec<PathBuf>,
+    pub file_open: bool,
+    /// Size of the last file when the checkpoint was taken, later writes are discarded on resume.
+    pub file_size: u64,
+    pub entries_in_file: usize,
+    /// Bytes written to the last file before compression, gzip dumps are re-encoded up to it.
+    #[serde(default)]
+    pub written: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchCheckpoint {
+    pub id: Uuid,
+    pub apps: Vec<String>,
+    pub env: Env,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub filter: Option<String>,
+    pub field_filter: Option<String>,
+    pub log_groups: Vec<String>,
+    pub filename: String,
+    pub dump: DumpOptions,
+    pub dump_state: Option<DumpState>,
+    pub progress: SearchProgress,
+    pub finished: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SearchProgress {
+    /// Progress written before searches were merged by timestamp only counts entries.
+    fn is_legacy(&self) -> bool {
+        self.last_timestamp.is_none() && self.log_count > 0
+    }
+}
+
+impl SearchCheckpoint {
+    fn dir() -> PathBuf {
+        wombat_dir().join("log-searches")
+    }
+
+    fn path(id: &Uuid) -> PathBuf {
+        SearchCheckpoint::dir().join(format!("{id}.json"))
+    }
+
+    pub fn save(&mut self) {
+        self.updated_at = Utc::now();
+        let result = fs::create_dir_all(SearchCheckpoint::dir()).and_then(|_| {
+            fs::write(
+                SearchCheckpoint::path(&self.id),
+                serde_json::to_string_pretty(self).expect("Failed to serialize search checkpoint"),
+            )
+        });
+        if let Err(e) = result {
+            warn!("Failed to store checkpoint of search {}, {e}", self.id);
+        }
+    }
+
+    pub fn load(id: &Uuid) -> Result<SearchCheckpoint, CommandError> {
+        let content = fs::read_to_string(SearchCheckpoint::path(id)).map_err(|e| {
+            CommandError::new("resume_find_logs", format!("Unknown search {id}, {e}"))
+        })?;
+        serde_json::from_str(&content)
+            .map(SearchCheckpoint::migrate)
+            .map_err(|e| {
+                CommandError::new("resume_find_logs", format!("Invalid checkpoint {id}, {e}"))
+            })
+    }
+
+    /// Legacy progress cannot be mapped to a timestamp, such searches restart into fresh files.
+    fn migrate(mut self) -> SearchCheckpoint {
+        if self.progress.is_legacy() {
+            warn!(
+                "Checkpoint of search {} predates timestamp progress, restarting it",
+                self.id
+            );
+            self.progress = SearchProgress::default();
+            self.dump_state = self.dump_state.map(|state| DumpState {
+                files: Vec::new(),
+                file_open: false,
+                file_size: 0,
+                entries_in_file: 0,
+                ..state
+            });
+        }
+        self
+    }
+
+    pub fn unfinished() -> Vec<SearchCheckpoint> {
+        let Ok(entries) = fs::read_dir(SearchCheckpoint::dir()) else {
+            return Vec::new();
+        };
+        let mut checkpoints: Vec<SearchCheckpoint> = entries
+            .flatten()
+            .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+            .filter_map(|content| serde_json::from_str::<SearchCheckpoint>(&content).ok())
+            .map(SearchCheckpoint::migrate)
+            .filter(|checkpoint| !checkpoint.finished)
+            .collect();
+        checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.updated_at));
+        checkpoints
+    }
+}
@@ -0,0 +1,511 @@
+use crate::aws::{LogEntry, LogSearchMonitor};
+use crate::log_checkpoint::{DumpState, SearchCheckpoint, SearchProgress};
+use crate::log_events::SearchEmitter;
+use crate::shared::CommandError;
+use chrono::{DateTime, SecondsFormat};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing_unwrap::ResultExt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpFormat {
+    #[default]
+    Ndjson,
+    Csv,
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DumpOptions {
+    pub format: DumpFormat,
+    pub gzip: bool,
+    /// Starts a new file once this many bytes were written, measured before compression.
+    pub max_file_size: Option<u64>,
+}
+
+pub trait LogFormat: Send {
+    fn extension(&self) -> &'static str;
+    fn header(&self) -> &'static str {
+        ""
+    }
+    fn entry(&self, log: &LogEntry, first_in_file: bool) -> String;
+    fn footer(&self) -> &'static str {
+        ""
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp_millis(timestamp)
+        .map(|timestamp| timestamp.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+pub struct Ndjson;
+
+impl LogFormat for Ndjson {
+    fn extension(&self) -> &'static str {
+        "log"
+    }
+    fn entry(&self, log: &LogEntry, _first_in_file: bool) -> String {
+        format!("{}\n", serde_json::to_string(log).unwrap_or_log())
+    }
+}
+
+pub struct Csv;
+
+impl Csv {
+    fn field(value: Option<&str>) -> String {
+        format!("\"{}\"", value.unwrap_or_default().replace('"', "\"\""))
+    }
+}
+
+impl LogFormat for Csv {
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+    fn header(&self) -> &'static str {
+        "timestamp,log_stream_name,level,logger,trace_id,message\n"
+    }
+    fn entry(&self, log: &LogEntry, _first_in_file: bool) -> String {
+        format!(
+            "{},{},{},{},{},{}\n",
+            format_timestamp(log.timestamp),
+            Csv::field(Some(&log.log_stream_name)),
+            Csv::field(log.fields.level.as_deref()),
+            Csv::field(log.fields.logger.as_deref()),
+            Csv::field(log.fields.trace_id.as_deref()),
+            Csv::field(Some(&log.message)),
+        )
+    }
+}
+
+pub struct Text;
+
+impl LogFormat for Text {
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+    fn entry(&self, log: &LogEntry, _first_in_file: bool) -> String {
+        format!(
+            "{} {} {}\n",
+            format_timestamp(log.timestamp),
+            log.log_stream_name,
+            log.message
+        )
+    }
+}
+
+pub struct JsonArray;
+
+impl LogFormat for JsonArray {
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+    fn header(&self) -> &'static str {
+        "[\n"
+    }
+    fn entry(&self, log: &LogEntry, first_in_file: bool) -> String {
+        let separator = if first_in_file { "" } else { ",\n" };
+        format!("{separator}{}", serde_json::to_string(log).unwrap_or_log())
+    }
+    fn footer(&self) -> &'static str {
+        "\n]\n"
+    }
+}
+
+enum Output {
+    Plain(BufWriter<fs::File>),
+    Gzip(GzEncoder<BufWriter<fs::File>>),
+}
+
+impl Output {
+    fn create(path: &Path, gzip: bool) -> io::Result<Output> {
+        let writer = BufWriter::new(fs::File::create(path)?);
+        Ok(match gzip {
+            true => Output::Gzip(GzEncoder::new(writer, Compression::default())),
+            false => Output::Plain(writer),
+        })
+    }
+
+    fn append(path: &Path, size: u64) -> io::Result<Output> {
+        let mut file = fs::OpenOptions::new().write(true).open(path)?;
+        file.set_len(size)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Output::Plain(BufWriter::new(file)))
+    }
+
+    /// A gzip member cannot be appended to, the first `written` bytes of the interrupted dump are
+    /// compressed again into a complete member that later writes continue.
+    fn reencode(path: &Path, written: u64) -> io::Result<Output> {
+        let kept = path.with_extension("resume");
+        {
+            let mut decoder =
+                MultiGzDecoder::new(BufReader::new(fs::File::open(path)?)).take(written);
+            let mut plain = BufWriter::new(fs::File::create(&kept)?);
+            let copied = io::copy(&mut decoder, &mut plain)?;
+            plain.flush()?;
+            if copied < written {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "dump is shorter than its checkpoint",
+                ));
+            }
+        }
+        let mut output = Output::create(path, true)?;
+        let mut reader = BufReader::new(fs::File::open(&kept)?);
+        loop {
+            let data = reader.fill_buf()?;
+            if data.is_empty() {
+                break;
+            }
+            let length = data.len();
+            output.write_all(data)?;
+            reader.consume(length);
+        }
+        fs::remove_file(&kept)?;
+        Ok(output)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(writer) => writer.flush(),
+            Output::Gzip(writer) => writer.flush(),
+        }
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Output::Plain(writer) => writer.write_all(data),
+            Output::Gzip(writer) => writer.write_all(data),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Plain(mut writer) => writer.flush(),
+            Output::Gzip(writer) => writer.finish()?.flush(),
+        }
+    }
+}
+
+pub struct FileDump<F: LogFormat> {
+    emitter: SearchEmitter,
+    format: F,
+    base_path: PathBuf,
+    gzip: bool,
+    max_file_size: Option<u64>,
+    output: Option<Output>,
+    written: u64,
+    entries_in_file: usize,
+    files: Vec<PathBuf>,
+    checkpoint: Option<SearchCheckpoint>,
+}
+
+enum DumpTarget {
+    New(PathBuf),
+    Resume(DumpState),
+}
+
+impl<F: LogFormat> FileDump<F> {
+    fn open(
+        emitter: SearchEmitter,
+        format: F,
+        target: DumpTarget,
+        options: &DumpOptions,
+        checkpoint: Option<SearchCheckpoint>,
+    ) -> io::Result<FileDump<F>> {
+        let base_path = match &target {
+            DumpTarget::New(base_path) => base_path.clone(),
+            DumpTarget::Resume(state) => state.base_path.clone(),
+        };
+        let mut dump = FileDump {
+            emitter,
+            format,
+            base_path,
+            gzip: options.gzip,
+            max_file_size: options.max_file_size.filter(|size| *size > 0),
+            output: None,
+            written: 0,
+            entries_in_file: 0,
+            files: Vec::new(),
+            checkpoint,
+        };
+        match target {
+            DumpTarget::New(_) => dump.open_next()?,
+            DumpTarget::Resume(state) => {
+                dump.files = state.files;
+                if state.file_open {
+                    if let Some(path) = dump.files.last() {
+                        let (output, written) = match (dump.gzip, state.written) {
+                            (false, _) => (Output::append(path, state.file_size)?, state.file_size),
+                            (true, Some(written)) => (Output::reencode(path, written)?, written),
+                            (true, None) => {
+                                return Err(io::Error::other(
+                                    "gzip dumps of searches checkpointed by an older version cannot be continued",
+                                ))
+                            }
+                        };
+                        dump.output = Some(output);
+                        dump.written = written;
+                        dump.entries_in_file = state.entries_in_file;
+                    }
+                }
+            }
+        }
+        Ok(dump)
+    }
+
+    fn dump_state(&mut self) -> io::Result<DumpState> {
+        if let Some(output) = self.output.as_mut() {
+            output.flush()?;
+        }
+        let file_size = match self.files.last() {
+            Some(path) => fs::metadata(path)?.len(),
+            None => 0,
+        };
+        Ok(DumpState {
+            base_path: self.base_path.clone(),
+            files: self.files.clone(),
+            file_open: self.output.is_some(),
+            file_size,
+            entries_in_file: self.entries_in_file,
+            written: Some(self.written),
+        })
+    }
+
+    fn open_next(&mut self) -> io::Result<()> {
+        let mut path: OsString = self.base_path.clone().into_os_string();
+        if !self.files.is_empty() {
+            path.push(format!(".{}", self.files.len() + 1));
+        }
+        path.push(format!(".{}", self.format.extension()));
+        if self.gzip {
+            path.push(".gz");
+        }
+        let path = PathBuf::from(path);
+        let mut output = Output::create(&path, self.gzip)?;
+        let header = self.format.header();
+        output.write_all(header.as_bytes())?;
+        self.output = Some(output);
+        self.written = header.len() as u64;
+        self.entries_in_file = 0;
+        self.files.push(path);
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        if let Some(mut output) = self.output.take() {
+            output.write_all(self.format.footer().as_bytes())?;
+            output.finish()?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, logs: &[LogEntry]) -> io::Result<()> {
+        for log in logs {
+            if self.output.is_none() {
+                self.open_next()?;
+            }
+            let data = self.format.entry(log, self.entries_in_file == 0);
+            if let Some(output) = self.output.as_mut() {
+                output.write_all(data.as_bytes())?;
+            }
+            self.written += data.len() as u64;
+            self.entries_in_file += 1;
+            if self.max_file_size.is_some_and(|max| self.written >= max) {
+                self.close()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn file_locations(&self) -> String {
+        self.files
+            .iter()
+            .map(|path| {
+                fs::canonicalize(path)
+                    .unwrap_or_else(|_| path.clone())
+                    .display()
+                    .to_string()
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn emit_entry(&self, log_stream_name: &str, message: String) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_log()
+            .as_millis() as i64;
+        self.emitter.emit(
+            "new-log-found",
+            vec![LogEntry::new(
+                log_stream_name.to_owned(),
+                None,
+                timestamp,
+                timestamp,
+                message,
+            )],
+        );
+    }
+}
+
+impl<F: LogFormat> LogSearchMonitor for FileDump<F> {
+    fn notify(&mut self, logs: Vec<LogEntry>) {
+        if let Err(e) = self.write(&logs) {
+            warn!("Failed to write logs to dump, {e}");
+        }
+        if let Some(log) = logs.first() {
+            self.emit_entry(
+                &log.log_stream_name,
+                format!("INFO Stored {} logs", logs.len()),
+            );
+        }
+    }
+    fn success(&mut self, msg: String) {
+        if let Err(e) = self.close() {
+            warn!("Failed to finish log dump, {e}");
+        }
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            checkpoint.finished = true;
+            checkpoint.save();
+        }
+        self.emit_entry("-", format!("TRACE File: {}", self.file_locations()));
+        self.emitter.emit("find-logs-success", msg);
+    }
+    fn error(&mut self, msg: String) {
+        if let Err(e) = self.close() {
+            warn!("Failed to finish log dump, {e}");
+        }
+        self.emit_entry("-", format!("ERROR {msg}"));
+        self.emitter.emit("find-logs-error", msg);
+    }
+    fn message(&mut self, msg: String) {
+        self.emit_entry("-", format!("INFO {msg}"));
+        self.emitter.emit("find-logs-message", msg);
+    }
+    fn checkpoint(&mut self, progress: &SearchProgress) {
+        if self.checkpoint.is_none() {
+            return;
+        }
+        match self.dump_state() {
+            Ok(dump_state) => {
+                if let Some(checkpoint) = self.checkpoint.as_mut() {
+                    checkpoint.dump_state = Some(dump_state);
+                    checkpoint.progress = progress.clone();
+                    checkpoint.save();
+                }
+            }
+            Err(e) => warn!("Failed to flush log dump for checkpoint, {e}"),
+        }
+    }
+}
+
+impl<F: LogFormat> Drop for FileDump<F> {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            warn!("Failed to finish log dump, {e}");
+        }
+    }
+}
+
+fn open_dump(
+    emitter: SearchEmitter,
+    target: DumpTarget,
+    options: &DumpOptions,
+    checkpoint: Option<SearchCheckpoint>,
+) -> Result<Box<dyn LogSearchMonitor>, CommandError> {
+    let to_command_error =
+        |e: io::Error| CommandError::new("find_logs", format!("Failed to open log dump, {e}"));
+    let monitor: Box<dyn LogSearchMonitor> = match options.format {
+        DumpFormat::Ndjson => Box::new(
+            FileDump::open(emitter, Ndjson, target, options, checkpoint)
+                .map_err(to_command_error)?,
+        ),
+        DumpFormat::Csv => Box::new(
+            FileDump::open(emitter, Csv, target, options, checkpoint).map_err(to_command_error)?,
+        ),
+        DumpFormat::Text => Box::new(
+            FileDump::open(emitter, Text, target, options, checkpoint).map_err(to_command_error)?,
+        ),
+        DumpFormat::Json => Box::new(
+            FileDump::open(emitter, JsonArray, target, options, checkpoint)
+                .map_err(to_command_error)?,
+        ),
+    };
+    Ok(monitor)
+}
+
+pub fn create(
+    emitter: SearchEmitter,
+    logs_dir: &Path,
+    filename: &str,
+    options: &DumpOptions,
+    checkpoint: Option<SearchCheckpoint>,
+) -> Result<Box<dyn LogSearchMonitor>, CommandError> {
+    fs::create_dir_all(logs_dir)
+        .map_err(|e| CommandError::new("find_logs", format!("Failed to create log dump, {e}")))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_log()
+        .as_millis();
+    let base_path = logs_dir.join(format!("{filename}-{timestamp}"));
+    open_dump(emitter, DumpTarget::New(base_path), options, checkpoint)
+}
+
+/// Reopens the dump of an interrupted search, dropping anything written after its checkpoint.
+pub fn resume(
+    emitter: SearchEmitter,
+    checkpoint: SearchCheckpoint,
+) -> Result<Box<dyn LogSearchMonitor>, CommandError> {
+    let state = checkpoint
+        .dump_state
+        .clone()
+        .ok_or_else(|| CommandError::new("resume_find_logs", "Search has no dump to continue"))?;
+    let options = checkpoint.dump.clone();
+    open_dump(
+        emitter,
+        DumpTarget::Resume(state),
+        &options,
+        Some(checkpoint),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reencode_keeps_checkpointed_part_of_interrupted_gzip_dump() {
+        let path = std::env::temp_dir().join(format!("wombat-{}.log.gz", uuid::Uuid::new_v4()));
+        let mut output = Output::create(&path, true).unwrap();
+        output.write_all(b"first\n").unwrap();
+        output.flush().unwrap();
+        output.write_all(b"after checkpoint\n").unwrap();
+        output.flush().unwrap();
+        // An interrupted search never finishes its gzip member.
+        std::mem::forget(output);
+
+        let mut output = Output::reencode(&path, 6).unwrap();
+        output.write_all(b"resumed\n").unwrap();
+        output.finish().unwrap();
+
+        let mut content = String::new();
+        MultiGzDecoder::new(fs::File::open(&path).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(content, "first\nresumed\n");
+    }
+}
@@ -0,0 +1,36 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchEvent<T> {
+    search_id: Uuid,
+    data: T,
+}
+
+/// Emits log search events tagged with the search they belong to, several searches can run at once.
+#[derive(Clone)]
+pub struct SearchEmitter {
+    app_handle: AppHandle,
+    pub search_id: Uuid,
+}
+
+impl SearchEmitter {
+    pub fn new(app_handle: AppHandle, search_id: Uuid) -> SearchEmitter {
+        SearchEmitter {
+            app_handle,
+            search_id,
+        }
+    }
+
+    pub fn emit<T: Serialize + Clone>(&self, event: &str, data: T) {
+        let _ = self.app_handle.emit(
+            event,
+            SearchEvent {
+                search_id: self.search_id,
+                data,
+            },
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::cache_db;
+use crate::user::wombat_dir;
+use crate::wombat_api::{LogFilter, LogFilterSource};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, Row};
+use serde::Deserialize;
+
+const CACHE_NAME: &str = "log_filters";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogFilterInput {
+    pub filter: String,
+    pub services: Vec<String>,
+    pub label: String,
+}
+
+impl LogFilterInput {
+    fn validate(&self) -> Result<(), String> {
+        if self.label.trim().is_empty() || self.filter.trim().is_empty() {
+            return Err("Log filter needs a label and a filter".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// Personal log filters, kept next to the shared ones served by the backend.
+pub struct LocalLogFilters {
+    db_pool: Pool<SqliteConnectionManager>,
+}
+
+impl LocalLogFilters {
+    pub fn open() -> Self {
+        let manager = SqliteConnectionManager::file(wombat_dir().join("log-filters.db"));
+        let db_pool = Pool::builder().max_size(2).build(manager).unwrap();
+        {
+            let conn = db_pool.get().unwrap();
+            Self::migrate(&conn);
+        }
+        LocalLogFilters { db_pool }
+    }
+
+    fn migrate(conn: &Connection) {
+        let version = cache_db::get_cache_version(conn, CACHE_NAME);
+        if version < 1 {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS log_filters(
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    filter TEXT NOT NULL,
+                    services TEXT NOT NULL,
+                    label TEXT NOT NULL
+                );",
+            )
+            .unwrap();
+            cache_db::set_cache_version(conn, CACHE_NAME, 1);
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<LogFilter> {
+        let services: String = row.get(2)?;
+        Ok(LogFilter {
+            id: row.get(0)?,
+            filter: row.get(1)?,
+            services: serde_json::from_str(&services).unwrap_or_default(),
+            label: row.get(3)?,
+            source: LogFilterSource::Local,
+        })
+    }
+
+    pub fn list(&self) -> Result<Vec<LogFilter>, String> {
+        let conn = self.db_pool.get().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT id, filter, services, label FROM log_filters ORDER BY label")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], Self::from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<LogFilter>, rusqlite::Error>>()
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn get(&self, id: i64) -> Result<LogFilter, String> {
+        let conn = self.db_pool.get().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id, filter, services, label FROM log_filters WHERE id = ?",
+            params![id],
+            Self::from_row,
+        )
+        .map_err(|e| format!("Log filter {id} not found, {e}"))
+    }
+
+    pub fn create(&self, input: LogFilterInput) -> Result<LogFilter, String> {
+        input.validate()?;
+        let conn = self.db_pool.get().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO log_filters (filter, services, label) VALUES (?, ?, ?)",
+            params![
+                input.filter,
+                serde_json::to_string(&input.services).map_err(|e| e.to_string())?,
+                input.label
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        self.get(conn.last_insert_rowid())
+    }
+
+    pub fn update(&self, id: i64, input: LogFilterInput) -> Result<LogFilter, String> {
+        input.validate()?;
+        let conn = self.db_pool.get().map_err(|e| e.to_string())?;
+        let updated = conn
+            .execute(
+                "UPDATE log_filters SET filter = ?, services = ?, label = ? WHERE id = ?",
+                params![
+                    input.filter,
+                    serde_json::to_string(&input.services).map_err(|e| e.to_string())?,
+                    input.label,
+                    id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(format!("Log filter {id} not found"));
+        }
+        self.get(id)
+    }
+
+    pub fn delete(&self, id: i64) -> Result<(), String> {
+        let conn = self.db_pool.get().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM log_filters WHERE id = ?", params![id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
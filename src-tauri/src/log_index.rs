@@ -0,0 +1,502 @@
+use crate::aws::LogEntry;
+use crate::cache_db;
+use crate::log_sources::LogNaming;
+use crate::shared::Env;
+use crate::user::wombat_dir;
+use chrono::DateTime;
+use flate2::read::MultiGzDecoder;
+use log::{info, warn};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, params_from_iter, Connection};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+const CACHE_NAME: &str = "log_index";
+pub const SEARCH_RESULT_LIMIT: usize = 10000;
+
+#[derive(Deserialize)]
+struct DumpedLog {
+    log_stream_name: String,
+    timestamp: i64,
+    ingestion_time: i64,
+    message: String,
+    #[serde(default)]
+    app: Option<String>,
+    env: Option<Env>,
+}
+
+/// Dump formats written by `log_dump`, told apart by file extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DumpFile {
+    Ndjson,
+    Csv,
+    Text,
+    Json,
+}
+
+impl DumpFile {
+    fn of(path: &Path) -> Option<DumpFile> {
+        let name = path.file_name()?.to_str()?;
+        let name = name.strip_suffix(".gz").unwrap_or(name);
+        match name.rsplit_once('.')?.1 {
+            "log" => Some(DumpFile::Ndjson),
+            "csv" => Some(DumpFile::Csv),
+            "txt" => Some(DumpFile::Text),
+            "json" => Some(DumpFile::Json),
+            _ => None,
+        }
+    }
+
+    fn read(
+        self,
+        reader: impl BufRead,
+        each: &mut dyn FnMut(DumpedLog) -> Result<(), String>,
+    ) -> Result<(), String> {
+        match self {
+            DumpFile::Ndjson => {
+                for line in reader.lines() {
+                    let line = line.map_err(|e| e.to_string())?;
+                    if let Ok(dumped) = serde_json::from_str::<DumpedLog>(&line) {
+                        each(dumped)?;
+                    }
+                }
+            }
+            DumpFile::Json => {
+                let dumped: Vec<DumpedLog> =
+                    serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+                for dumped in dumped {
+                    each(dumped)?;
+                }
+            }
+            DumpFile::Csv => {
+                let mut record = String::new();
+                for line in reader.lines().skip(1) {
+                    let line = line.map_err(|e| e.to_string())?;
+                    if !record.is_empty() {
+                        record.push('\n');
+                    }
+                    record.push_str(&line);
+                    // Quoted messages may span several lines.
+                    if record.matches('"').count() % 2 == 1 {
+                        continue;
+                    }
+                    let fields = csv_fields(&std::mem::take(&mut record));
+                    if let [timestamp, log_stream_name, _, _, _, message] = fields.as_slice() {
+                        if let Some(timestamp) = parse_timestamp(timestamp) {
+                            each(DumpedLog::plain(
+                                timestamp,
+                                log_stream_name,
+                                message.clone(),
+                            ))?;
+                        }
+                    }
+                }
+            }
+            DumpFile::Text => {
+                let mut pending: Option<DumpedLog> = None;
+                for line in reader.lines() {
+                    let line = line.map_err(|e| e.to_string())?;
+                    let entry = line.split_once(' ').and_then(|(timestamp, rest)| {
+                        let timestamp = parse_timestamp(timestamp)?;
+                        let (log_stream_name, message) = rest.split_once(' ').unwrap_or((rest, ""));
+                        Some(DumpedLog::plain(
+                            timestamp,
+                            log_stream_name,
+                            message.to_owned(),
+                        ))
+                    });
+                    match entry {
+                        Some(entry) => {
+                            if let Some(previous) = pending.replace(entry) {
+                                each(previous)?;
+                            }
+                        }
+                        // Lines not starting with a timestamp continue a multiline message.
+                        None => {
+                            if let Some(previous) = pending.as_mut() {
+                                previous.message.push('\n');
+                                previous.message.push_str(&line);
+                            }
+                        }
+                    }
+                }
+                if let Some(previous) = pending {
+                    each(previous)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DumpedLog {
+    /// Entry of a dump format that does not keep ingestion time, app or env.
+    fn plain(timestamp: i64, log_stream_name: &str, message: String) -> DumpedLog {
+        DumpedLog {
+            log_stream_name: log_stream_name.to_owned(),
+            timestamp,
+            ingestion_time: timestamp,
+            message,
+            app: None,
+            env: None,
+        }
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.timestamp_millis())
+        .ok()
+}
+
+fn csv_fields(record: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Outcome of indexing the dumps directory.
+pub struct IngestSummary {
+    pub ingested: usize,
+    /// Dumps that could not be indexed, with the reason.
+    pub failed: Vec<String>,
+}
+
+pub struct LogIndex {
+    db_pool: Pool<SqliteConnectionManager>,
+}
+
+impl LogIndex {
+    pub fn open() -> Result<Self, String> {
+        let manager = SqliteConnectionManager::file(wombat_dir().join("log-index.db"));
+        let db_pool = Pool::builder()
+            .max_size(2)
+            .build(manager)
+            .map_err(|e| format!("Failed to open log index, {e}"))?;
+        {
+            let conn = db_pool
+                .get()
+                .map_err(|e| format!("Failed to open log index, {e}"))?;
+            Self::migrate(&conn).map_err(|e| format!("Failed to migrate log index, {e}"))?;
+        }
+        Ok(LogIndex { db_pool })
+    }
+
+    fn migrate(conn: &Connection) -> Result<(), rusqlite::Error> {
+        let version = cache_db::get_cache_version(conn, CACHE_NAME);
+        if version < 1 {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS indexed_files(
+                    path TEXT PRIMARY KEY NOT NULL,
+                    size INTEGER NOT NULL,
+                    modified INTEGER NOT NULL
+                );
+                CREATE VIRTUAL TABLE IF NOT EXISTS log_entries USING fts5(
+                    message,
+                    trace_id,
+                    log_stream_name UNINDEXED,
+                    app UNINDEXED,
+                    env UNINDEXED,
+                    level UNINDEXED,
+                    timestamp UNINDEXED,
+                    ingestion_time UNINDEXED,
+                    source UNINDEXED
+                );",
+            )?;
+            cache_db::set_cache_version(conn, CACHE_NAME, 1);
+        }
+        if version < 2 {
+            // Apps used to be guessed from the stream layout, dumps are indexed again.
+            conn.execute_batch("DELETE FROM log_entries; DELETE FROM indexed_files;")?;
+            cache_db::set_cache_version(conn, CACHE_NAME, 2);
+        }
+        Ok(())
+    }
+
+    /// Indexes dumps in `logs_dir` whose size or modification time changed since they were last
+    /// indexed and drops entries of removed dumps.
+    /// Dumps written before entries carried their app get it from the `naming` rules.
+    pub fn ingest_dir(&self, logs_dir: &Path, naming: &LogNaming) -> Result<IngestSummary, String> {
+        let mut conn = self.db_pool.get().map_err(|e| e.to_string())?;
+        let mut summary = IngestSummary {
+            ingested: 0,
+            failed: Vec::new(),
+        };
+        let entries = match fs::read_dir(logs_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                info!("log dumps dir not readable, {e}");
+                return Ok(summary);
+            }
+        };
+        let mut present = HashSet::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(format) = DumpFile::of(&path) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let size = metadata.len() as i64;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|modified| modified.as_millis() as i64)
+                .unwrap_or_default();
+            let source = path.display().to_string();
+            present.insert(source.clone());
+            let known: Option<(i64, i64)> = conn
+                .query_row(
+                    "SELECT size, modified FROM indexed_files WHERE path = ?",
+                    params![source],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .ok();
+            if known == Some((size, modified)) {
+                continue;
+            }
+            match Self::ingest_file(&mut conn, &path, format, &source, size, modified, naming) {
+                Ok(count) => {
+                    info!("indexed {count} logs from {source}");
+                    summary.ingested += count;
+                }
+                Err(e) => {
+                    warn!("failed to index {source}, {e}");
+                    summary.failed.push(format!("{source} ({e})"));
+                }
+            }
+        }
+        Self::remove_missing(&mut conn, &present).map_err(|e| e.to_string())?;
+        Ok(summary)
+    }
+
+    fn remove_missing(conn: &mut Connection, present: &HashSet<String>) -> rusqlite::Result<()> {
+        let indexed: Vec<String> = conn
+            .prepare("SELECT path FROM indexed_files")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        let tx = conn.transaction()?;
+        for source in indexed.iter().filter(|source| !present.contains(*source)) {
+            info!("dropping index of removed dump {source}");
+            tx.execute("DELETE FROM log_entries WHERE source = ?", params![source])?;
+            tx.execute("DELETE FROM indexed_files WHERE path = ?", params![source])?;
+        }
+        tx.commit()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn ingest_file(
+        conn: &mut Connection,
+        path: &Path,
+        format: DumpFile,
+        source: &str,
+        size: i64,
+        modified: i64,
+        naming: &LogNaming,
+    ) -> Result<usize, String> {
+        let file = fs::File::open(path).map_err(|e| e.to_string())?;
+        let reader: Box<dyn Read> = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Box::new(MultiGzDecoder::new(file)),
+            _ => Box::new(file),
+        };
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM log_entries WHERE source = ?", params![source])
+            .map_err(|e| e.to_string())?;
+        let mut count = 0;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO log_entries
+                        (message, trace_id, log_stream_name, app, env, level, timestamp, ingestion_time, source)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )
+                .map_err(|e| e.to_string())?;
+            format.read(BufReader::new(reader), &mut |dumped: DumpedLog| {
+                let app = dumped
+                    .app
+                    .or_else(|| naming.app_of(&dumped.log_stream_name));
+                let mut log = LogEntry::new(
+                    dumped.log_stream_name,
+                    app,
+                    dumped.timestamp,
+                    dumped.ingestion_time,
+                    dumped.message,
+                );
+                log.env = dumped.env;
+                insert
+                    .execute(params![
+                        log.message,
+                        log.fields.trace_id,
+                        log.log_stream_name,
+                        log.app,
+                        log.env.map(|env| env.to_string()),
+                        log.fields.level,
+                        log.timestamp,
+                        log.ingestion_time,
+                        source,
+                    ])
+                    .map_err(|e| e.to_string())?;
+                count += 1;
+                Ok(())
+            })?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO indexed_files (path, size, modified) VALUES (?, ?, ?)",
+            params![source, size, modified],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(count)
+    }
+
+    /// Full text search over indexed dumps, `query` uses the FTS5 query syntax.
+    pub fn search(
+        &self,
+        query: &str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+        apps: &[String],
+    ) -> Result<Vec<LogEntry>, String> {
+        let conn = self.db_pool.get().map_err(|e| e.to_string())?;
+        let mut sql = String::from(
+            "SELECT log_stream_name, app, timestamp, ingestion_time, message, env FROM log_entries
+            WHERE CAST(timestamp AS INTEGER) BETWEEN ? AND ?",
+        );
+        let mut values: Vec<rusqlite::types::Value> =
+            vec![start_timestamp.into(), end_timestamp.into()];
+        if !query.trim().is_empty() {
+            sql.push_str(" AND log_entries MATCH ?");
+            values.push(query.to_owned().into());
+        }
+        if !apps.is_empty() {
+            sql.push_str(&format!(
+                " AND app IN ({})",
+                vec!["?"; apps.len()].join(",")
+            ));
+            values.extend(apps.iter().map(|app| app.clone().into()));
+        }
+        sql.push_str(&format!(
+            " ORDER BY CAST(timestamp AS INTEGER) LIMIT {SEARCH_RESULT_LIMIT}"
+        ));
+
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                let mut log = LogEntry::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                );
+                log.env = row
+                    .get::<_, Option<String>>(5)?
+                    .map(|env| Env::from_exact(&env))
+                    .filter(|env| env != &Env::DEVNULL);
+                Ok(log)
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<LogEntry>, rusqlite::Error>>()
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(format: DumpFile, dump: &str) -> Vec<(i64, String, String)> {
+        let mut logs = Vec::new();
+        format
+            .read(dump.as_bytes(), &mut |dumped: DumpedLog| {
+                logs.push((dumped.timestamp, dumped.log_stream_name, dumped.message));
+                Ok(())
+            })
+            .unwrap();
+        logs
+    }
+
+    #[test]
+    fn recognizes_dump_formats() {
+        assert_eq!(DumpFile::of(Path::new("a.log.gz")), Some(DumpFile::Ndjson));
+        assert_eq!(DumpFile::of(Path::new("a.csv")), Some(DumpFile::Csv));
+        assert_eq!(DumpFile::of(Path::new("a-1.txt.gz")), Some(DumpFile::Text));
+        assert_eq!(DumpFile::of(Path::new("a.json")), Some(DumpFile::Json));
+        assert_eq!(DumpFile::of(Path::new("checkpoint.toml")), None);
+    }
+
+    #[test]
+    fn reads_csv_with_multiline_messages() {
+        let dump = "timestamp,log_stream_name,level,logger,trace_id,message\n\
+            \"2024-05-01T10:00:00.000Z\",\"web/app/1\",\"\",\"\",\"\",\"said \"\"hi\"\"\"\n\
+            \"2024-05-01T10:00:01.000Z\",\"web/app/1\",\"\",\"\",\"\",\"Failed, twice\n\tat Foo\"\n";
+        assert_eq!(
+            read(DumpFile::Csv, dump),
+            vec![
+                (
+                    1714557600000,
+                    "web/app/1".to_owned(),
+                    "said \"hi\"".to_owned()
+                ),
+                (
+                    1714557601000,
+                    "web/app/1".to_owned(),
+                    "Failed, twice\n\tat Foo".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_text_with_multiline_messages() {
+        let dump = "2024-05-01T10:00:00.000Z web/app/1 Failed\n\tat Foo\n\
+            2024-05-01T10:00:01.000Z web/app/2 Done in 3 ms\n";
+        assert_eq!(
+            read(DumpFile::Text, dump),
+            vec![
+                (
+                    1714557600000,
+                    "web/app/1".to_owned(),
+                    "Failed\n\tat Foo".to_owned()
+                ),
+                (
+                    1714557601000,
+                    "web/app/2".to_owned(),
+                    "Done in 3 ms".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_json_arrays() {
+        let dump = r#"[
+{"log_stream_name":"web/app/1","timestamp":1,"ingestion_time":2,"message":"a","app":"app","env":null}
+]"#;
+        assert_eq!(
+            read(DumpFile::Json, dump),
+            vec![(1, "web/app/1".to_owned(), "a".to_owned())]
+        );
+    }
+}
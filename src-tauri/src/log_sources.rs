@@ -0,0 +1,198 @@
+use crate::shared::Env;
+use aws_sdk_cloudwatchlogs as cloudwatchlogs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogNamingRule {
+    pub app_type: String,
+    /// Log group name prefix, `{env}` is replaced with the searched env.
+    pub group_prefix: String,
+    /// Log stream name prefix, `{app}` is replaced with the searched app.
+    pub stream_prefix: String,
+    /// Apps following this rule, a rule without apps applies to all remaining apps.
+    #[serde(default)]
+    pub apps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogNaming {
+    pub rules: Vec<LogNamingRule>,
+}
+
+impl Default for LogNaming {
+    fn default() -> Self {
+        LogNaming {
+            rules: vec![LogNamingRule {
+                app_type: "web".to_owned(),
+                group_prefix: "dsi-{env}-".to_owned(),
+                stream_prefix: "web/{app}/".to_owned(),
+                apps: Vec::new(),
+            }],
+        }
+    }
+}
+
+impl LogNaming {
+    fn rule_for(&self, app: &str) -> Option<&LogNamingRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.apps.iter().any(|rule_app| rule_app == app))
+            .or_else(|| self.rules.iter().find(|rule| rule.apps.is_empty()))
+    }
+
+    pub fn group_prefixes(&self, env: &Env, apps: &[String]) -> Vec<String> {
+        apps.iter()
+            .filter_map(|app| self.rule_for(app))
+            .map(|rule| rule.group_prefix.replace("{env}", &env.to_string()))
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect()
+    }
+
+    pub fn stream_prefix(&self, app: &str) -> Option<String> {
+        self.rule_for(app)
+            .map(|rule| rule.stream_prefix.replace("{app}", app))
+    }
+
+    /// App whose stream prefix the stream name starts with, the inverse of `stream_prefix`.
+    pub fn app_of(&self, stream_name: &str) -> Option<String> {
+        self.rules.iter().find_map(|rule| {
+            let (head, tail) = rule.stream_prefix.split_once("{app}")?;
+            let rest = stream_name.strip_prefix(head)?;
+            // Without anything after `{app}` the app ends at the next path segment.
+            let end = match tail.is_empty() {
+                true => rest.find('/').unwrap_or(rest.len()),
+                false => rest.find(tail)?,
+            };
+            let app = &rest[..end];
+            (!app.is_empty() && self.rule_for(app) == Some(rule)).then(|| app.to_owned())
+        })
+    }
+}
+
+/// Where logs of the searched apps live, explicit log groups skip naming rules entirely.
+#[derive(Debug, Clone, Default)]
+pub struct LogSources {
+    pub naming: LogNaming,
+    pub log_groups: Vec<String>,
+}
+
+impl LogSources {
+    pub fn uses_explicit_groups(&self) -> bool {
+        !self.log_groups.is_empty()
+    }
+
+    /// Stream name prefix per app, or `None` when every stream in the groups is searched.
+    pub fn stream_prefixes(&self, apps: &[String]) -> Option<Vec<(String, String)>> {
+        if self.uses_explicit_groups() {
+            return None;
+        }
+        Some(
+            apps.iter()
+                .filter_map(|app| {
+                    self.naming
+                        .stream_prefix(app)
+                        .map(|prefix| (app.clone(), prefix))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn app_of(&self, stream_name: &str) -> Option<String> {
+        self.naming.app_of(stream_name)
+    }
+
+    pub fn app_for_stream<'a>(&self, stream_name: &str, apps: &'a [String]) -> Option<&'a String> {
+        apps.iter().find(|app| {
+            self.naming
+                .stream_prefix(app)
+                .is_some_and(|prefix| stream_name.starts_with(&prefix))
+        })
+    }
+
+    pub async fn group_names(
+        &self,
+        client: &cloudwatchlogs::Client,
+        env: &Env,
+        apps: &[String],
+    ) -> Result<Vec<String>, String> {
+        if self.uses_explicit_groups() {
+            return Ok(self
+                .log_groups
+                .iter()
+                .map(|group| group.replace("{env}", &env.to_string()))
+                .collect());
+        }
+        let mut group_names = BTreeSet::new();
+        for prefix in self.naming.group_prefixes(env, apps) {
+            let mut marker = None;
+            loop {
+                let response = client
+                    .describe_log_groups()
+                    .log_group_name_prefix(&prefix)
+                    .set_next_token(marker)
+                    .send()
+                    .await
+                    .map_err(|err| format!("Error: {}", err.into_service_error()))?;
+                group_names.extend(
+                    response
+                        .log_groups()
+                        .iter()
+                        .filter_map(|group| group.log_group_name().map(|name| name.to_owned())),
+                );
+                marker = response.next_token().map(|m| m.to_owned());
+                if marker.is_none() {
+                    break;
+                }
+            }
+        }
+        Ok(group_names.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naming() -> LogNaming {
+        LogNaming {
+            rules: vec![
+                LogNamingRule {
+                    app_type: "lambda".to_owned(),
+                    group_prefix: "/aws/lambda/{env}-".to_owned(),
+                    stream_prefix: "{app}".to_owned(),
+                    apps: vec!["mailer".to_owned()],
+                },
+                LogNamingRule {
+                    app_type: "web".to_owned(),
+                    group_prefix: "dsi-{env}-".to_owned(),
+                    stream_prefix: "web/{app}/".to_owned(),
+                    apps: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn app_of_inverts_stream_prefix() {
+        let naming = naming();
+        assert_eq!(
+            naming.app_of("web/orders/ecs/1a2b"),
+            Some("orders".to_owned())
+        );
+        assert_eq!(
+            naming.app_of(&naming.stream_prefix("mailer").unwrap()),
+            Some("mailer".to_owned())
+        );
+    }
+
+    #[test]
+    fn app_of_ignores_streams_of_other_rules() {
+        let naming = naming();
+        assert_eq!(naming.app_of("2024/05/01/[$LATEST]1a2b"), None);
+        assert_eq!(naming.app_of("web/mailer/ecs/1a2b"), None);
+        assert_eq!(naming.app_of("api/orders/1a2b"), None);
+    }
+}
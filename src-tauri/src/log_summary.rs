@@ -0,0 +1,257 @@
+use crate::aws::{LogEntry, LogSearchMonitor, SearchWarning};
+use crate::log_checkpoint::SearchProgress;
+use crate::log_events::SearchEmitter;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+static UUID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}")
+        .unwrap()
+});
+static HEX_ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(0x)?[0-9a-fA-F]{8,}\b").unwrap());
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+(\.\d+)?").unwrap());
+
+const MESSAGE_KEYS: [&str; 3] = ["message", "msg", "@message"];
+const TEMPLATE_MAX_LENGTH: usize = 300;
+const SUMMARY_MAX_TEMPLATES: usize = 200;
+const SUMMARY_HISTOGRAM_BUCKETS: i64 = 60;
+const SUMMARY_EMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryMode {
+    /// Raw entries are still delivered next to the summary.
+    #[default]
+    Alongside,
+    Instead,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryOptions {
+    #[serde(default)]
+    pub mode: SummaryMode,
+    pub bucket_ms: Option<i64>,
+}
+
+impl SummaryOptions {
+    /// Fills in a bucket size splitting the searched range into roughly even histogram bars.
+    pub fn for_range(mut self, start_timestamp: i64, end_timestamp: i64) -> SummaryOptions {
+        if self.bucket_ms.is_none_or(|bucket_ms| bucket_ms <= 0) {
+            let range = (end_timestamp - start_timestamp).max(0);
+            self.bucket_ms = Some((range / SUMMARY_HISTOGRAM_BUCKETS).max(1000));
+        }
+        self
+    }
+}
+
+/// Masks the variable parts of a message so similar lines share one template.
+pub fn template(message: &str) -> String {
+    let text = match serde_json::from_str::<Value>(message) {
+        Ok(Value::Object(object)) => MESSAGE_KEYS
+            .iter()
+            .find_map(|key| object.get(*key).and_then(|value| value.as_str()))
+            .map(|text| text.to_owned())
+            .unwrap_or_else(|| message.to_owned()),
+        _ => message.to_owned(),
+    };
+    let first_line = text.lines().next().unwrap_or_default();
+    let masked = UUID.replace_all(first_line, "<uuid>");
+    let masked = HEX_ID.replace_all(&masked, "<id>");
+    let masked = NUMBER.replace_all(&masked, "<num>");
+    masked.chars().take(TEMPLATE_MAX_LENGTH).collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSummary {
+    pub template: String,
+    pub level: Option<String>,
+    pub count: usize,
+    pub example: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub apps: BTreeMap<String, usize>,
+    pub buckets: BTreeMap<i64, usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBucket {
+    pub timestamp: i64,
+    pub count: usize,
+    pub apps: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSummary {
+    pub total: usize,
+    pub bucket_ms: i64,
+    pub templates: Vec<TemplateSummary>,
+    pub histogram: Vec<HistogramBucket>,
+    pub finished: bool,
+}
+
+pub struct LogSummarizer {
+    emitter: SearchEmitter,
+    inner: Box<dyn LogSearchMonitor>,
+    mode: SummaryMode,
+    bucket_ms: i64,
+    total: usize,
+    templates: HashMap<String, TemplateSummary>,
+    histogram: BTreeMap<i64, HistogramBucket>,
+    last_emit: Option<Instant>,
+}
+
+impl LogSummarizer {
+    pub fn new(
+        emitter: SearchEmitter,
+        inner: Box<dyn LogSearchMonitor>,
+        options: &SummaryOptions,
+    ) -> LogSummarizer {
+        LogSummarizer {
+            emitter,
+            inner,
+            mode: options.mode,
+            bucket_ms: options.bucket_ms.filter(|ms| *ms > 0).unwrap_or(60_000),
+            total: 0,
+            templates: HashMap::new(),
+            histogram: BTreeMap::new(),
+            last_emit: None,
+        }
+    }
+
+    fn add(&mut self, log: &LogEntry) {
+        let app = log.app.clone().unwrap_or(String::from("-"));
+        let bucket = log.timestamp - log.timestamp.rem_euclid(self.bucket_ms);
+        let summary = self
+            .templates
+            .entry(template(&log.message))
+            .or_insert_with_key(|template| TemplateSummary {
+                template: template.clone(),
+                level: log.fields.level.clone(),
+                count: 0,
+                example: log.message.clone(),
+                first_seen: log.timestamp,
+                last_seen: log.timestamp,
+                apps: BTreeMap::new(),
+                buckets: BTreeMap::new(),
+            });
+        summary.count += 1;
+        summary.first_seen = summary.first_seen.min(log.timestamp);
+        summary.last_seen = summary.last_seen.max(log.timestamp);
+        *summary.apps.entry(app.clone()).or_default() += 1;
+        *summary.buckets.entry(bucket).or_default() += 1;
+
+        let histogram_bucket = self
+            .histogram
+            .entry(bucket)
+            .or_insert_with(|| HistogramBucket {
+                timestamp: bucket,
+                count: 0,
+                apps: BTreeMap::new(),
+            });
+        histogram_bucket.count += 1;
+        *histogram_bucket.apps.entry(app).or_default() += 1;
+        self.total += 1;
+    }
+
+    fn summary(&self, finished: bool) -> LogSummary {
+        let mut templates: Vec<TemplateSummary> = self.templates.values().cloned().collect();
+        templates.sort_by(|a, b| b.count.cmp(&a.count).then(a.template.cmp(&b.template)));
+        templates.truncate(SUMMARY_MAX_TEMPLATES);
+        LogSummary {
+            total: self.total,
+            bucket_ms: self.bucket_ms,
+            templates,
+            histogram: self.histogram.values().cloned().collect(),
+            finished,
+        }
+    }
+
+    fn emit(&mut self, finished: bool) {
+        let due = self
+            .last_emit
+            .is_none_or(|last_emit| last_emit.elapsed() >= SUMMARY_EMIT_INTERVAL);
+        if !finished && !due {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+        self.emitter.emit("log-summary", self.summary(finished));
+    }
+}
+
+impl LogSearchMonitor for LogSummarizer {
+    fn notify(&mut self, logs: Vec<LogEntry>) {
+        logs.iter().for_each(|log| self.add(log));
+        self.emit(false);
+        if self.mode == SummaryMode::Alongside {
+            self.inner.notify(logs);
+        }
+    }
+    fn success(&mut self, msg: String) {
+        self.emit(true);
+        self.inner.success(msg);
+    }
+    fn error(&mut self, msg: String) {
+        self.emit(true);
+        self.inner.error(msg);
+    }
+    fn message(&mut self, msg: String) {
+        self.inner.message(msg);
+    }
+    fn checkpoint(&mut self, progress: &SearchProgress) {
+        self.inner.checkpoint(progress);
+    }
+    fn warning(&mut self, warning: SearchWarning) {
+        self.inner.warning(warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_masks_numbers() {
+        assert_eq!(
+            template("User 42 logged in after 3.5s"),
+            "User <num> logged in after <num>s"
+        );
+    }
+
+    #[test]
+    fn template_masks_ids() {
+        assert_eq!(
+            template("Order 123e4567-e89b-12d3-a456-426614174000 shipped"),
+            "Order <uuid> shipped"
+        );
+        assert_eq!(
+            template("trace deadbeef42 done, ptr 0x7ffe12ab"),
+            "trace <id> done, ptr <id>"
+        );
+    }
+
+    #[test]
+    fn template_reads_message_of_structured_logs() {
+        assert_eq!(
+            template(r#"{"level":"INFO","message":"Took 15 ms"}"#),
+            "Took <num> ms"
+        );
+        assert_eq!(template(r#"{"level":"INFO"}"#), r#"{"level":"INFO"}"#);
+    }
+
+    #[test]
+    fn template_keeps_first_line_only() {
+        assert_eq!(
+            template("Failed 3 times\n\tat Foo.bar"),
+            "Failed <num> times"
+        );
+        assert_eq!(template(&"x".repeat(400)).len(), TEMPLATE_MAX_LENGTH);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::aws::{self, LogEntry, LogSearchMonitor};
+use crate::shared::Env;
+use crate::{
+    run_log_search, AppContextState, AwsConfigProviderInstance, LogSearchRequest, UserConfigState,
+};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+const LOG_WATCH_HISTORY: usize = 20;
+/// Failed checks in a row after which a watch is disabled, single failures are often transient.
+const LOG_WATCH_MAX_FAILURES: u32 = 3;
+const LOG_WATCH_TICK: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogWatchResult {
+    pub checked_at: DateTime<Utc>,
+    /// Matches in the checked window, counting stops once the threshold is exceeded.
+    pub count: usize,
+    pub triggered: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogWatch {
+    pub id: Uuid,
+    pub name: String,
+    pub apps: Vec<String>,
+    pub env: Env,
+    pub filter: Option<String>,
+    pub interval_minutes: u32,
+    pub threshold: usize,
+    pub enabled: bool,
+    #[serde(default)]
+    pub history: VecDeque<LogWatchResult>,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+}
+
+impl LogWatch {
+    /// Watches saved before intervals were validated may carry 0, they run every minute.
+    fn window_minutes(&self) -> i64 {
+        i64::from(self.interval_minutes.max(1))
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled
+            && self.history.back().is_none_or(|last| {
+                now - last.checked_at >= chrono::Duration::minutes(self.window_minutes())
+            })
+    }
+
+    fn was_triggered(&self) -> bool {
+        self.history.back().is_some_and(|last| last.triggered)
+    }
+
+    /// Adds a check result to the history, returns true when it disabled the watch.
+    pub fn record(&mut self, result: LogWatchResult) -> bool {
+        self.consecutive_failures = match result.error {
+            Some(_) => self.consecutive_failures + 1,
+            None => 0,
+        };
+        self.history.push_back(result);
+        while self.history.len() > LOG_WATCH_HISTORY {
+            self.history.pop_front();
+        }
+        let disable = self.enabled && self.consecutive_failures >= LOG_WATCH_MAX_FAILURES;
+        if disable {
+            self.enabled = false;
+        }
+        disable
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogWatchInput {
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub apps: Vec<String>,
+    pub env: Env,
+    pub filter: Option<String>,
+    pub interval_minutes: u32,
+    pub threshold: usize,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogWatchEvent {
+    watch_id: Uuid,
+    name: String,
+    result: LogWatchResult,
+}
+
+#[derive(Default)]
+struct CountingMonitor {
+    count: usize,
+    error: Option<String>,
+}
+
+impl LogSearchMonitor for CountingMonitor {
+    fn notify(&mut self, logs: Vec<LogEntry>) {
+        self.count += logs.len();
+    }
+    fn success(&mut self, _msg: String) {}
+    fn error(&mut self, msg: String) {
+        self.error = Some(msg);
+    }
+    fn message(&mut self, _msg: String) {}
+}
+
+async fn check(app_handle: &AppHandle, watch: &LogWatch) -> LogWatchResult {
+    let checked_at = Utc::now();
+    let end_timestamp = checked_at.timestamp_millis();
+    let start_timestamp = end_timestamp - watch.window_minutes() * 60 * 1000;
+    let request = LogSearchRequest {
+        apps: watch.apps.clone(),
+        env: watch.env.clone(),
+        start_timestamp,
+        end_timestamp,
+        filter: watch.filter.clone(),
+        filename: None,
+        mode: Some(aws::LogSearchMode::Filter),
+        field_filter: None,
+        dump: None,
+        log_groups: None,
+        summary: None,
+        search_id: None,
+        limit: Some(aws::SearchLimit::abort(watch.threshold)),
+        resume_from: None,
+    };
+    let monitor = Arc::new(Mutex::new(CountingMonitor::default()));
+    let _ = run_log_search(app_handle, request, monitor.clone()).await;
+    let monitor = monitor.lock().await;
+    let triggered = monitor.count >= watch.threshold;
+    LogWatchResult {
+        checked_at,
+        count: monitor.count,
+        triggered,
+        // Exceeding the limit is reported as an error by the search, it is what the watch looks for.
+        error: monitor.error.clone().filter(|_| !triggered),
+    }
+}
+
+async fn sso_config(app_handle: &AppHandle, env: &Env) -> (String, aws_config::SdkConfig) {
+    let aws_config_provider = app_handle.state::<AwsConfigProviderInstance>();
+    let aws_config_provider = aws_config_provider.0.read().await;
+    aws_config_provider.sso_config(env).await
+}
+
+/// Checks the SSO session of `env` without triggering a login, watches run in the background.
+async fn is_logged(app_handle: &AppHandle, env: &Env) -> bool {
+    let (profile, config) = sso_config(app_handle, env).await;
+    aws::is_logged(&profile, &config, true).await
+}
+
+async fn run_due_watches(app_handle: &AppHandle) {
+    let watches: Vec<LogWatch> = {
+        let user_config = app_handle.state::<UserConfigState>();
+        let user_config = user_config.0.lock().await;
+        let now = Utc::now();
+        user_config
+            .log_watches
+            .iter()
+            .filter(|watch| watch.is_due(now))
+            .cloned()
+            .collect()
+    };
+    let mut logged_envs: HashMap<Env, bool> = HashMap::new();
+    for watch in watches {
+        let logged = match logged_envs.get(&watch.env) {
+            Some(logged) => *logged,
+            None => {
+                let logged = is_logged(app_handle, &watch.env).await;
+                logged_envs.insert(watch.env.clone(), logged);
+                logged
+            }
+        };
+        // An expired session would fail every check, they wait for the next login instead.
+        if !logged {
+            info!(
+                "Skipping log watch {}, no valid session on {}",
+                &watch.name, &watch.env
+            );
+            continue;
+        }
+        info!("Running log watch {}", &watch.name);
+        let result = check(app_handle, &watch).await;
+        if result.triggered && !watch.was_triggered() {
+            let _ = app_handle
+                .notification()
+                .builder()
+                .title(format!("Log watch {} triggered", &watch.name))
+                .body(format!(
+                    "{}+ logs matched in {} ({}) within the last {} minute(s)",
+                    result.count,
+                    watch.apps.join(", "),
+                    watch.env,
+                    watch.window_minutes()
+                ))
+                .show()
+                .inspect_err(|e| warn!("Failed to show log watch notification, {e}"));
+            let _ = app_handle.emit(
+                "log-watch-triggered",
+                LogWatchEvent {
+                    watch_id: watch.id,
+                    name: watch.name.clone(),
+                    result: result.clone(),
+                },
+            );
+        }
+        let user_config = app_handle.state::<UserConfigState>();
+        let disabled = user_config
+            .0
+            .lock()
+            .await
+            .record_log_watch_result(&watch.id, result.clone());
+        if disabled {
+            warn!(
+                "Disabled log watch {} after {LOG_WATCH_MAX_FAILURES} failed checks",
+                &watch.name
+            );
+            let _ = app_handle
+                .notification()
+                .builder()
+                .title(format!("Log watch {} disabled", &watch.name))
+                .body(format!(
+                    "Its last {LOG_WATCH_MAX_FAILURES} checks failed: {}",
+                    result.error.clone().unwrap_or_default()
+                ))
+                .show()
+                .inspect_err(|e| warn!("Failed to show log watch notification, {e}"));
+            let _ = app_handle.emit(
+                "log-watch-disabled",
+                LogWatchEvent {
+                    watch_id: watch.id,
+                    name: watch.name.clone(),
+                    result,
+                },
+            );
+        }
+    }
+}
+
+/// Checks due watches until aborted, nothing runs while no profile is active.
+pub async fn run_scheduler(app_handle: AppHandle) {
+    loop {
+        tokio::time::sleep(LOG_WATCH_TICK).await;
+        let app_state = app_handle.state::<AppContextState>();
+        if app_state.0.lock().await.active_profile.is_none() {
+            continue;
+        }
+        run_due_watches(&app_handle).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(interval_minutes: u32, checked_at: DateTime<Utc>) -> LogWatch {
+        LogWatch {
+            id: Uuid::new_v4(),
+            name: String::from("errors"),
+            apps: vec![String::from("app")],
+            env: Env::DEV,
+            filter: None,
+            interval_minutes,
+            threshold: 1,
+            enabled: true,
+            history: VecDeque::from([LogWatchResult {
+                checked_at,
+                count: 0,
+                triggered: false,
+                error: None,
+            }]),
+            consecutive_failures: 0,
+        }
+    }
+
+    fn result(error: Option<&str>) -> LogWatchResult {
+        LogWatchResult {
+            checked_at: Utc::now(),
+            count: 0,
+            triggered: false,
+            error: error.map(|error| error.to_owned()),
+        }
+    }
+
+    #[test]
+    fn disables_watch_after_consecutive_failures_only() {
+        let mut watch = watch(1, Utc::now());
+        assert!(!watch.record(result(Some("throttled"))));
+        assert!(!watch.record(result(None)));
+        assert!(!watch.record(result(Some("throttled"))));
+        assert!(!watch.record(result(Some("throttled"))));
+        assert!(watch.enabled);
+        assert!(watch.record(result(Some("throttled"))));
+        assert!(!watch.enabled);
+        assert!(!watch.record(result(Some("throttled"))));
+    }
+
+    #[test]
+    fn is_due_after_interval() {
+        let now = Utc::now();
+        let watch = watch(5, now - chrono::Duration::minutes(4));
+        assert!(!watch.is_due(now));
+        assert!(watch.is_due(now + chrono::Duration::minutes(1)));
+    }
+
+    #[test]
+    fn zero_interval_waits_a_minute() {
+        let now = Utc::now();
+        assert!(!watch(0, now).is_due(now));
+        assert!(watch(0, now - chrono::Duration::minutes(1)).is_due(now));
+    }
+}
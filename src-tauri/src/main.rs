@@ -1,6 +1,6 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use aws::{Cluster, DbSecret, InfraProfile, LogEntry, RdsInstance, SsoProfile};
+use aws::{Cluster, DbSecret, InfraProfile, LogSearchMonitor, RdsInstance, SsoProfile};
 use chrono::{DateTime, Utc};
 use cluster_resolver::ClusterResolver;
 #[cfg(debug_assertions)]
@@ -13,13 +13,12 @@ use rds_resolver::RdsResolver;
 use sha2::{Digest, Sha256};
 use shared::{arn_to_name, BrowserExtension, CommandError, CookieJar, Env};
 use shared_child::SharedChild;
-use std::collections::{HashMap, HashSet};
-use std::io::{BufWriter, Write};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::process::Command;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 use std::{env, fs};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::{Mutex, RwLock};
 use tracing_unwrap::{OptionExt, ResultExt};
 use urlencoding::encode;
@@ -28,22 +27,40 @@ use user::UserConfig;
 mod aws;
 mod cache_db;
 mod cluster_resolver;
+mod control_api;
 mod dependency_check;
 mod ecs_resolver;
+mod event_stream;
+mod log_checkpoint;
+mod log_dump;
+mod log_events;
+mod log_filters;
+mod log_index;
+mod log_sources;
+mod log_summary;
+mod log_watch;
 mod proxy;
 mod proxy_authenticators;
 mod rds_resolver;
 mod requirements;
 mod rest_api;
+mod rest_api_auth;
 mod shared;
+mod structured_logs;
 mod user;
 mod wombat_api;
 
-#[derive(Clone, serde::Serialize)]
-struct TaskKilled {
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TaskKilled {
     arn: String,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ProxyStarted {
+    arn: String,
+    port: u16,
+}
+
 #[derive(Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 struct NewTaskParams {
@@ -51,8 +68,8 @@ struct NewTaskParams {
     proxy_auth_config: Option<wombat_api::ProxyAuthConfig>,
 }
 
-#[derive(Clone, serde::Serialize)]
-struct ServiceDetailsPayload {
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ServiceDetailsPayload {
     app: String,
     services: Vec<Result<aws::ServiceDetails, aws::ServiceDetailsMissing>>,
     dbs: Vec<aws::RdsInstance>,
@@ -134,11 +151,37 @@ async fn browser_extension_health(
     Ok(browser_ext.to_status())
 }
 
+#[tauri::command]
+async fn rest_api_pairing_code(
+    rest_api_auth: tauri::State<'_, RestApiAuthInstance>,
+) -> Result<rest_api_auth::PairingCode, CommandError> {
+    let mut rest_api_auth = rest_api_auth.0.lock().await;
+    Ok(rest_api_auth.new_pairing_code())
+}
+
+#[tauri::command]
+async fn rest_api_clients(
+    rest_api_auth: tauri::State<'_, RestApiAuthInstance>,
+) -> Result<Vec<rest_api_auth::PairedClientInfo>, CommandError> {
+    let rest_api_auth = rest_api_auth.0.lock().await;
+    Ok(rest_api_auth.clients())
+}
+
+#[tauri::command]
+async fn revoke_rest_api_client(
+    client_id: uuid::Uuid,
+    rest_api_auth: tauri::State<'_, RestApiAuthInstance>,
+) -> Result<Vec<rest_api_auth::PairedClientInfo>, CommandError> {
+    let mut rest_api_auth = rest_api_auth.0.lock().await;
+    rest_api_auth.revoke(client_id)?;
+    Ok(rest_api_auth.clients())
+}
+
 #[tauri::command]
 async fn cookie_jar_status(
     cookie_jar: tauri::State<'_, CookieJarInstance>,
 ) -> Result<shared::CookieJarStatus, ()> {
-    let cookie_jar = cookie_jar.0.lock().await;
+    let mut cookie_jar = cookie_jar.0.lock().await;
     Ok(cookie_jar.to_status())
 }
 
@@ -272,6 +315,16 @@ async fn login(
         }
     }));
 
+    {
+        let mut task_tracker = task_tracker.0.lock().await;
+        if let Some(handler) = &task_tracker.log_watch_scheduler {
+            handler.abort();
+        }
+        task_tracker.log_watch_scheduler = Some(tokio::task::spawn(log_watch::run_scheduler(
+            app_handle.clone(),
+        )));
+    }
+
     let _ = app_handle.emit("message", "Success!");
 
     Ok(user_config.clone())
@@ -286,6 +339,15 @@ async fn set_dbeaver_path(
     user_config.set_dbeaver_path(dbeaver_path)
 }
 
+#[tauri::command]
+async fn set_rest_api_port(
+    port: u16,
+    user_config: tauri::State<'_, UserConfigState>,
+) -> Result<UserConfig, CommandError> {
+    let mut user_config = user_config.0.lock().await;
+    user_config.set_rest_api_port(port)
+}
+
 #[tauri::command]
 async fn set_logs_dir_path(
     logs_dir: &str,
@@ -418,6 +480,7 @@ async fn stop_job(
         return Err(CommandError::new("stop_job", msg));
     };
     let mut tracker = async_task_tracker.0.lock().await;
+    tracker.request_handlers.remove(arn);
     if let Some(handle) = tracker.task_handlers.remove(arn) {
         let kill_result = handle.send(());
         if kill_result.is_ok() {
@@ -492,11 +555,15 @@ async fn logout(
     if let Some(handler) = &home_details_refresher.aws_resource_refresher {
         handler.abort()
     }
+    if let Some(handler) = &home_details_refresher.log_watch_scheduler {
+        handler.abort()
+    }
     let jobs = &mut home_details_refresher.proxies_handlers;
     for job in jobs.drain() {
         let _ = job.1.kill();
         let _ = job.1.wait();
     }
+    home_details_refresher.request_handlers.clear();
 
     app_state.active_profile = None;
     app_state.last_auth_check = 0;
@@ -505,195 +572,767 @@ async fn logout(
 }
 
 struct WindowNotifier {
-    app_handle: AppHandle,
+    emitter: log_events::SearchEmitter,
 }
 
-impl aws::LogSearchMonitor for WindowNotifier {
+impl LogSearchMonitor for WindowNotifier {
     fn notify(&mut self, logs: Vec<aws::LogEntry>) {
-        let _ = self.app_handle.emit("new-log-found", logs);
+        self.emitter.emit("new-log-found", logs);
     }
     fn success(&mut self, msg: String) {
-        let _ = self.app_handle.emit("find-logs-success", msg);
+        self.emitter.emit("find-logs-success", msg);
     }
     fn error(&mut self, msg: String) {
-        let _ = self.app_handle.emit("find-logs-error", msg);
+        self.emitter.emit("find-logs-error", msg);
     }
     fn message(&mut self, msg: String) {
-        let _ = self.app_handle.emit("find-logs-message", msg);
+        self.emitter.emit("find-logs-message", msg);
+    }
+    fn warning(&mut self, warning: aws::SearchWarning) {
+        self.emitter.emit("find-logs-warning", warning);
+    }
+}
+
+fn parse_field_filter(
+    field_filter: Option<String>,
+) -> Result<Option<structured_logs::FieldFilter>, CommandError> {
+    match field_filter {
+        Some(field_filter) if !field_filter.trim().is_empty() => {
+            structured_logs::FieldFilter::parse(&field_filter).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn log_sources(
+    log_groups: Option<Vec<String>>,
+    user_config: &UserConfigState,
+    aws_config_provider: &AwsConfigProviderInstance,
+    wombat_api: &WombatApiInstance,
+) -> log_sources::LogSources {
+    let profile_name = aws_config_provider
+        .0
+        .read()
+        .await
+        .active_wombat_profile
+        .name
+        .clone();
+    let profile_naming = user_config.0.lock().await.log_naming(&profile_name);
+    let naming = match profile_naming {
+        Some(naming) => naming,
+        None => wombat_api
+            .0
+            .lock()
+            .await
+            .log_naming()
+            .await
+            .unwrap_or_default(),
+    };
+    log_sources::LogSources {
+        naming,
+        log_groups: searched_log_groups(log_groups),
     }
 }
 
-struct FileNotifier {
+fn searched_log_groups(log_groups: Option<Vec<String>>) -> Vec<String> {
+    log_groups
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|group| !group.trim().is_empty())
+        .collect()
+}
+
+#[tauri::command]
+async fn log_naming(
+    user_config: tauri::State<'_, UserConfigState>,
+    aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+    wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+) -> Result<log_sources::LogNaming, CommandError> {
+    let sources = log_sources(
+        None,
+        &user_config,
+        &aws_config_provider,
+        &wombat_api_instance,
+    )
+    .await;
+    Ok(sources.naming)
+}
+
+#[tauri::command]
+async fn log_watches(
+    user_config: tauri::State<'_, UserConfigState>,
+) -> Result<Vec<log_watch::LogWatch>, CommandError> {
+    Ok(user_config.0.lock().await.log_watches.clone())
+}
+
+#[tauri::command]
+async fn save_log_watch(
+    watch: log_watch::LogWatchInput,
+    user_config: tauri::State<'_, UserConfigState>,
+) -> Result<UserConfig, CommandError> {
+    user_config.0.lock().await.save_log_watch(watch)
+}
+
+#[tauri::command]
+async fn delete_log_watch(
+    id: uuid::Uuid,
+    user_config: tauri::State<'_, UserConfigState>,
+) -> Result<UserConfig, CommandError> {
+    user_config.0.lock().await.delete_log_watch(&id)
+}
+
+#[tauri::command]
+async fn set_log_naming(
+    log_naming: Option<log_sources::LogNaming>,
+    user_config: tauri::State<'_, UserConfigState>,
+    aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+) -> Result<UserConfig, CommandError> {
+    let mut user_config = user_config.0.lock().await;
+    let aws_config_provider = aws_config_provider.0.read().await;
+    user_config.set_log_naming(&aws_config_provider.active_wombat_profile.name, log_naming)
+}
+
+fn search_limit(
+    filename: &Option<String>,
+    summary: &Option<log_summary::SummaryOptions>,
+    limit: Option<aws::SearchLimit>,
+) -> Option<aws::SearchLimit> {
+    let summary_only = summary
+        .as_ref()
+        .is_some_and(|summary| summary.mode == log_summary::SummaryMode::Instead);
+    aws::SearchLimit::for_search(limit, filename.is_some(), summary_only)
+}
+
+async fn track_search(
+    async_task_tracker: &AsyncTaskManager,
+    search_id: uuid::Uuid,
+    handler: tokio::task::JoinHandle<()>,
+) {
+    let mut tracker = async_task_tracker.0.lock().await;
+    tracker
+        .search_log_handlers
+        .retain(|_, handler| !handler.is_finished());
+    if let Some(previous) = tracker.search_log_handlers.insert(search_id, handler) {
+        previous.abort();
+    }
+}
+
+async fn log_search_monitor(
+    emitter: &log_events::SearchEmitter,
+    filename: &Option<String>,
+    dump: Option<log_dump::DumpOptions>,
+    checkpoint: Option<log_checkpoint::SearchCheckpoint>,
+    summary: Option<log_summary::SummaryOptions>,
+    user_config: &UserConfigState,
+) -> Result<Arc<Mutex<dyn LogSearchMonitor>>, CommandError> {
+    let monitor: Box<dyn LogSearchMonitor> = match filename {
+        None => Box::new(WindowNotifier {
+            emitter: emitter.clone(),
+        }),
+        Some(filename) => {
+            let logs_dir = user_config.0.lock().await.logs_dir.clone();
+            log_dump::create(
+                emitter.clone(),
+                &logs_dir,
+                filename,
+                &dump.unwrap_or_default(),
+                checkpoint,
+            )?
+        }
+    };
+    let monitor: Box<dyn LogSearchMonitor> = match summary {
+        Some(summary) => Box::new(log_summary::LogSummarizer::new(
+            emitter.clone(),
+            monitor,
+            &summary,
+        )),
+        None => monitor,
+    };
+    // Searches apply the field filter themselves, before entries count toward the limit.
+    Ok(structured_logs::filtered_monitor(monitor, None))
+}
+
+/// A log search, as started by the app, the control api or a log watch.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSearchRequest {
+    pub apps: Vec<String>,
+    pub env: Env,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub filter: Option<String>,
+    pub filename: Option<String>,
+    pub mode: Option<aws::LogSearchMode>,
+    pub field_filter: Option<String>,
+    pub dump: Option<log_dump::DumpOptions>,
+    pub log_groups: Option<Vec<String>>,
+    pub summary: Option<log_summary::SummaryOptions>,
+    pub search_id: Option<uuid::Uuid>,
+    pub limit: Option<aws::SearchLimit>,
+    pub resume_from: Option<log_checkpoint::SearchProgress>,
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn find_logs(
     app_handle: AppHandle,
-    writer: BufWriter<fs::File>,
-    filename_location: String,
+    apps: Vec<String>,
+    env: Env,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    filter: String,
+    filename: Option<String>,
+    mode: Option<aws::LogSearchMode>,
+    field_filter: Option<String>,
+    dump: Option<log_dump::DumpOptions>,
+    log_groups: Option<Vec<String>>,
+    summary: Option<log_summary::SummaryOptions>,
+    search_id: Option<uuid::Uuid>,
+    limit: Option<aws::SearchLimit>,
+    resume_from: Option<log_checkpoint::SearchProgress>,
+) -> Result<uuid::Uuid, CommandError> {
+    start_log_search(
+        &app_handle,
+        LogSearchRequest {
+            apps,
+            env,
+            start_timestamp,
+            end_timestamp,
+            filter: Some(filter),
+            filename,
+            mode,
+            field_filter,
+            dump,
+            log_groups,
+            summary,
+            search_id,
+            limit,
+            resume_from,
+        },
+    )
+    .await
 }
 
-impl aws::LogSearchMonitor for FileNotifier {
-    fn notify(&mut self, logs: Vec<aws::LogEntry>) {
-        let writer = &mut self.writer;
-        let mut data = "".to_owned();
-        for log in logs.iter() {
-            let log_str = serde_json::to_string(log).unwrap_or_log();
-            data.push_str(&log_str);
-            data.push('\n')
+/// Starts `request` in the background and returns its search id, results are emitted to the
+/// window or dumped to the requested file.
+pub async fn start_log_search(
+    app_handle: &AppHandle,
+    mut request: LogSearchRequest,
+) -> Result<uuid::Uuid, CommandError> {
+    if let Err(msg) = get_authorized(app_handle, &app_handle.state::<AppContextState>().0).await {
+        return Err(CommandError::new("find_logs", msg));
+    }
+
+    let search_id = *request.search_id.get_or_insert_with(uuid::Uuid::new_v4);
+    let emitter = log_events::SearchEmitter::new(app_handle.clone(), search_id);
+    let mode = request.mode.unwrap_or_default();
+    if request.resume_from.is_some() && mode != aws::LogSearchMode::Filter {
+        return Err(CommandError::new(
+            "find_logs",
+            "Only filter searches can be continued",
+        ));
+    }
+    // Invalid field filters fail the command rather than the started search.
+    parse_field_filter(request.field_filter.clone())?;
+    let user_config = app_handle.state::<UserConfigState>();
+    // Only filter searches dumped to a file can be resumed.
+    let checkpoint = match (&request.filename, mode) {
+        (Some(filename), aws::LogSearchMode::Filter) => Some(log_checkpoint::SearchCheckpoint {
+            id: search_id,
+            apps: request.apps.clone(),
+            env: request.env.clone(),
+            start_timestamp: request.start_timestamp,
+            end_timestamp: request.end_timestamp,
+            filter: request.filter.clone().filter(|filter| !filter.is_empty()),
+            field_filter: request.field_filter.clone(),
+            log_groups: searched_log_groups(request.log_groups.clone()),
+            filename: filename.clone(),
+            dump: request.dump.clone().unwrap_or_default(),
+            dump_state: None,
+            progress: request.resume_from.clone().unwrap_or_default(),
+            finished: false,
+            updated_at: chrono::Utc::now(),
+        }),
+        _ => None,
+    };
+    let monitor = log_search_monitor(
+        &emitter,
+        &request.filename,
+        request.dump.clone(),
+        checkpoint,
+        request
+            .summary
+            .clone()
+            .map(|summary| summary.for_range(request.start_timestamp, request.end_timestamp)),
+        &user_config,
+    )
+    .await?;
+
+    let search_app_handle = app_handle.clone();
+    let handler = tokio::task::spawn(async move {
+        let _ = run_log_search(&search_app_handle, request, monitor).await;
+    });
+    track_search(&app_handle.state::<AsyncTaskManager>(), search_id, handler).await;
+
+    Ok(search_id)
+}
+
+/// Runs `request` to its end, reporting the found logs to `monitor`.
+pub async fn run_log_search(
+    app_handle: &AppHandle,
+    request: LogSearchRequest,
+    monitor: Arc<Mutex<dyn LogSearchMonitor>>,
+) -> Result<usize, CommandError> {
+    let field_filter = match parse_field_filter(request.field_filter) {
+        Ok(field_filter) => field_filter,
+        Err(e) => {
+            monitor.lock().await.error(e.message.clone());
+            return Err(e);
         }
-        let _ = writer.write(data.as_bytes());
-        if let Some(log) = logs.first() {
-            let now = SystemTime::now();
-            let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_log();
-            let _ = self.app_handle.emit(
-                "new-log-found",
-                vec![LogEntry {
-                    log_stream_name: log.log_stream_name.to_owned(),
-                    ingestion_time: log.ingestion_time,
-                    timestamp: i64::try_from(timestamp.as_millis()).unwrap(),
-                    message: format!("INFO Stored {} logs", logs.len()),
-                }],
-            );
+    };
+    let limit = search_limit(&request.filename, &request.summary, request.limit);
+    let filter = request.filter.filter(|filter| !filter.is_empty());
+    let sources = log_sources(
+        request.log_groups,
+        &app_handle.state::<UserConfigState>(),
+        &app_handle.state::<AwsConfigProviderInstance>(),
+        &app_handle.state::<WombatApiInstance>(),
+    )
+    .await;
+    let sdk_config: aws_config::SdkConfig;
+    {
+        let aws_config_provider = app_handle.state::<AwsConfigProviderInstance>();
+        let aws_config_provider = aws_config_provider.0.read().await;
+        let app_config = aws_config_provider.sso_config(&request.env).await;
+        sdk_config = app_config.1;
+    }
+    match request.mode.unwrap_or_default() {
+        aws::LogSearchMode::Filter => {
+            aws::find_logs(
+                &sdk_config,
+                request.env,
+                request.apps,
+                &sources,
+                request.start_timestamp,
+                request.end_timestamp,
+                filter,
+                field_filter.as_ref(),
+                monitor,
+                limit,
+                request.resume_from,
+            )
+            .await
+        }
+        aws::LogSearchMode::Insights => {
+            aws::find_logs_insights(
+                &sdk_config,
+                request.env,
+                request.apps,
+                &sources,
+                request.start_timestamp,
+                request.end_timestamp,
+                filter,
+                field_filter.as_ref(),
+                monitor,
+                limit,
+            )
+            .await
         }
     }
-    fn success(&mut self, msg: String) {
-        let now = SystemTime::now();
-        let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_log();
-        let _ = self.app_handle.emit(
-            "new-log-found",
-            vec![LogEntry {
-                log_stream_name: "-".to_owned(),
-                ingestion_time: i64::try_from(timestamp.as_millis()).unwrap(),
-                timestamp: i64::try_from(timestamp.as_millis()).unwrap(),
-                message: format!("TRACE File: {}", self.filename_location),
-            }],
-        );
-        let _ = self.app_handle.emit("find-logs-success", msg);
-        let _ = self.writer.flush();
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn resume_find_logs(
+    app_handle: AppHandle,
+    search_id: uuid::Uuid,
+    app_state: tauri::State<'_, AppContextState>,
+    async_task_tracker: tauri::State<'_, AsyncTaskManager>,
+    user_config: tauri::State<'_, UserConfigState>,
+    aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+    wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+) -> Result<uuid::Uuid, CommandError> {
+    if let Err(msg) = get_authorized(&app_handle, &app_state.0).await {
+        return Err(CommandError::new("resume_find_logs", msg));
     }
-    fn error(&mut self, msg: String) {
-        let now = SystemTime::now();
-        let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_log();
-        let _ = self.app_handle.emit(
-            "new-log-found",
-            vec![LogEntry {
-                log_stream_name: "-".to_owned(),
-                ingestion_time: i64::try_from(timestamp.as_millis()).unwrap(),
-                timestamp: i64::try_from(timestamp.as_millis()).unwrap(),
-                message: format!("ERROR {msg}"),
-            }],
-        );
-        let _ = self.app_handle.emit("find-logs-error", msg);
-        let _ = self.writer.flush();
+    let checkpoint = log_checkpoint::SearchCheckpoint::load(&search_id)?;
+    if checkpoint.finished {
+        return Err(CommandError::new(
+            "resume_find_logs",
+            format!("Search {search_id} already finished"),
+        ));
     }
-    fn message(&mut self, msg: String) {
-        let now = SystemTime::now();
-        let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_log();
-        let _ = self.app_handle.emit(
-            "new-log-found",
-            vec![LogEntry {
-                log_stream_name: "-".to_owned(),
-                ingestion_time: i64::try_from(timestamp.as_millis()).unwrap(),
-                timestamp: i64::try_from(timestamp.as_millis()).unwrap(),
-                message: format!("INFO {msg}"),
-            }],
-        );
-        let _ = self.app_handle.emit("find-logs-message", msg);
+
+    let sources = log_sources(
+        Some(checkpoint.log_groups.clone()),
+        &user_config,
+        &aws_config_provider,
+        &wombat_api_instance,
+    )
+    .await;
+    let field_filter = parse_field_filter(checkpoint.field_filter.clone())?;
+    let env = checkpoint.env.clone();
+    let apps = checkpoint.apps.clone();
+    let filter = checkpoint.filter.clone();
+    let (start_timestamp, end_timestamp) = (checkpoint.start_timestamp, checkpoint.end_timestamp);
+    let progress = checkpoint.progress.clone();
+    let monitor = structured_logs::filtered_monitor(
+        log_dump::resume(
+            log_events::SearchEmitter::new(app_handle.clone(), search_id),
+            checkpoint,
+        )?,
+        None,
+    );
+
+    let sdk_config: aws_config::SdkConfig;
+    {
+        let aws_config_provider = aws_config_provider.0.read().await;
+        let app_config = aws_config_provider.sso_config(&env).await;
+        sdk_config = app_config.1;
     }
+    let handler = tokio::task::spawn(async move {
+        let _ = aws::find_logs(
+            &sdk_config,
+            env,
+            apps,
+            &sources,
+            start_timestamp,
+            end_timestamp,
+            filter,
+            field_filter.as_ref(),
+            monitor,
+            None,
+            Some(progress),
+        )
+        .await;
+    });
+    track_search(&async_task_tracker, search_id, handler).await;
+
+    Ok(search_id)
+}
+
+#[tauri::command]
+async fn log_search_checkpoints() -> Result<Vec<log_checkpoint::SearchCheckpoint>, CommandError> {
+    Ok(log_checkpoint::SearchCheckpoint::unfinished())
 }
 
 #[allow(clippy::too_many_arguments)]
 #[tauri::command]
-async fn find_logs(
+async fn trace_request(
     app_handle: AppHandle,
-    apps: Vec<String>,
+    trace_id: String,
     env: Env,
     start_timestamp: i64,
     end_timestamp: i64,
+    filename: Option<String>,
+    dump: Option<log_dump::DumpOptions>,
+    search_id: Option<uuid::Uuid>,
+    limit: Option<aws::SearchLimit>,
+    app_state: tauri::State<'_, AppContextState>,
+    async_task_tracker: tauri::State<'_, AsyncTaskManager>,
+    user_config: tauri::State<'_, UserConfigState>,
+    aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+    wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+    ecs_resolver_instance: tauri::State<'_, EcsResolverInstance>,
+) -> Result<uuid::Uuid, CommandError> {
+    if let Err(msg) = get_authorized(&app_handle, &app_state.0).await {
+        return Err(CommandError::new("trace_request", msg));
+    }
+    let trace_id = trace_id.trim().to_owned();
+    if !structured_logs::is_valid_trace_id(&trace_id) {
+        return Err(CommandError::new(
+            "trace_request",
+            format!("Invalid trace id: {trace_id}"),
+        ));
+    }
+    let apps: Vec<String> = ecs_resolver_instance
+        .0
+        .read()
+        .await
+        .read_services()
+        .await
+        .into_iter()
+        .filter(|service| service.env == env)
+        .map(|service| service.name)
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect();
+    if apps.is_empty() {
+        return Err(CommandError::new(
+            "trace_request",
+            format!("No apps known on {env}"),
+        ));
+    }
+
+    let limit = search_limit(&filename, &None, limit);
+    let sources = log_sources(
+        None,
+        &user_config,
+        &aws_config_provider,
+        &wombat_api_instance,
+    )
+    .await;
+    // Windows does not allow ':' in file names.
+    let filename = filename.map(|filename| format!("{filename}-{}", trace_id.replace(':', "_")));
+    let search_id = search_id.unwrap_or_else(uuid::Uuid::new_v4);
+    let emitter = log_events::SearchEmitter::new(app_handle, search_id);
+    let monitor = log_search_monitor(&emitter, &filename, dump, None, None, &user_config).await?;
+
+    let sdk_config = aws_config_provider.0.read().await.sso_config(&env).await.1;
+    let handler = tokio::task::spawn(async move {
+        // Hits from all app streams come out of the search merged by timestamp.
+        let _ = aws::find_logs(
+            &sdk_config,
+            env,
+            apps,
+            &sources,
+            start_timestamp,
+            end_timestamp,
+            Some(format!("\"{trace_id}\"")),
+            None,
+            monitor,
+            limit,
+            None,
+        )
+        .await;
+    });
+    track_search(&async_task_tracker, search_id, handler).await;
+
+    Ok(search_id)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn find_logs_across_envs(
+    app_handle: AppHandle,
+    apps: Vec<String>,
+    envs: Vec<Env>,
+    start_timestamp: i64,
+    end_timestamp: i64,
     filter: String,
     filename: Option<String>,
+    mode: Option<aws::LogSearchMode>,
+    field_filter: Option<String>,
+    dump: Option<log_dump::DumpOptions>,
+    log_groups: Option<Vec<String>>,
+    summary: Option<log_summary::SummaryOptions>,
+    search_id: Option<uuid::Uuid>,
+    limit: Option<aws::SearchLimit>,
     app_state: tauri::State<'_, AppContextState>,
     async_task_tracker: tauri::State<'_, AsyncTaskManager>,
     user_config: tauri::State<'_, UserConfigState>,
     aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
-) -> Result<(), CommandError> {
+    wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+) -> Result<uuid::Uuid, CommandError> {
     if let Err(msg) = get_authorized(&app_handle, &app_state.0).await {
-        return Err(CommandError::new("find_logs", msg));
+        return Err(CommandError::new("find_logs_across_envs", msg));
     }
+    if envs.is_empty() {
+        return Err(CommandError::new(
+            "find_logs_across_envs",
+            "At least one env is required",
+        ));
+    }
+
+    let limit = search_limit(&filename, &summary, limit);
+    let field_filter = parse_field_filter(field_filter)?;
+    let sources = log_sources(
+        log_groups,
+        &user_config,
+        &aws_config_provider,
+        &wombat_api_instance,
+    )
+    .await;
+    let search_id = search_id.unwrap_or_else(uuid::Uuid::new_v4);
+    let emitter = log_events::SearchEmitter::new(app_handle, search_id);
+    let monitor = log_search_monitor(
+        &emitter,
+        &filename,
+        dump,
+        None,
+        summary.map(|summary| summary.for_range(start_timestamp, end_timestamp)),
+        &user_config,
+    )
+    .await?;
 
+    let mut configs = Vec::new();
     {
-        let handler = &async_task_tracker.0.lock().await.search_log_handler;
-        if let Some(handler) = handler {
-            handler.abort();
+        let aws_config_provider = aws_config_provider.0.read().await;
+        for env in envs.into_iter().collect::<BTreeSet<Env>>() {
+            let sdk_config = aws_config_provider.sso_config(&env).await.1;
+            configs.push((env, sdk_config));
         }
     }
-    {
-        async_task_tracker.0.lock().await.search_log_handler = None;
+
+    let handler = tokio::task::spawn(async move {
+        let _ = aws::find_logs_across_envs(
+            configs,
+            apps,
+            &sources,
+            start_timestamp,
+            end_timestamp,
+            if filter.is_empty() {
+                None
+            } else {
+                Some(filter)
+            },
+            field_filter.as_ref(),
+            mode.unwrap_or_default(),
+            monitor,
+            limit,
+        )
+        .await;
+    });
+    track_search(&async_task_tracker, search_id, handler).await;
+
+    Ok(search_id)
+}
+
+#[tauri::command]
+async fn tail_logs(
+    app_handle: AppHandle,
+    apps: Vec<String>,
+    env: Env,
+    filter: String,
+    field_filter: Option<String>,
+    log_groups: Option<Vec<String>>,
+    search_id: Option<uuid::Uuid>,
+    app_state: tauri::State<'_, AppContextState>,
+    async_task_tracker: tauri::State<'_, AsyncTaskManager>,
+    user_config: tauri::State<'_, UserConfigState>,
+    aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+    wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+) -> Result<uuid::Uuid, CommandError> {
+    if let Err(msg) = get_authorized(&app_handle, &app_state.0).await {
+        return Err(CommandError::new("tail_logs", msg));
     }
+    let field_filter = parse_field_filter(field_filter)?;
+    let sources = log_sources(
+        log_groups,
+        &user_config,
+        &aws_config_provider,
+        &wombat_api_instance,
+    )
+    .await;
 
-    let user_config = Arc::clone(&user_config.0);
     let sdk_config: aws_config::SdkConfig;
     {
         let aws_config_provider = aws_config_provider.0.read().await;
-        //TODO: it will be annoying to do to search logs with different sdk_configs...
-        //Let's hope it will keep working.
-        let app_config = aws_config_provider.sso_config(&env).await;
-        sdk_config = app_config.1;
+        sdk_config = aws_config_provider.sso_config(&env).await.1;
     }
-    async_task_tracker.0.lock().await.search_log_handler = Some(tokio::task::spawn(async move {
-        let _ = aws::find_logs(
+
+    let search_id = search_id.unwrap_or_else(uuid::Uuid::new_v4);
+    let emitter = log_events::SearchEmitter::new(app_handle, search_id);
+    let handler = tokio::task::spawn(async move {
+        let _ = aws::tail_logs(
             &sdk_config,
             env,
             apps,
-            start_timestamp,
-            end_timestamp,
+            &sources,
             if filter.is_empty() {
                 None
             } else {
                 Some(filter)
             },
-            match &filename {
-                None => Arc::new(Mutex::new(WindowNotifier { app_handle })),
-                Some(filename) => {
-                    let now = SystemTime::now();
-                    let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_log();
-
-                    let user = user_config.lock().await;
-                    let logs_dir = user.logs_dir.clone();
-                    fs::create_dir_all(&logs_dir).unwrap_or_log();
-                    let pathbuf =
-                        logs_dir.join(format!("{}-{}.log", filename, timestamp.as_millis()));
-                    let file = fs::File::create(pathbuf.clone()).unwrap_or_log();
-
-                    Arc::new(Mutex::new(FileNotifier {
-                        app_handle,
-                        writer: BufWriter::new(file),
-                        filename_location: format!(
-                            "{}",
-                            fs::canonicalize(&pathbuf).unwrap_or_log().display()
-                        ),
-                    }))
-                }
-            },
-            match &filename.is_some() {
-                false => Some(10000),
-                true => None,
-            },
+            structured_logs::filtered_monitor(Box::new(WindowNotifier { emitter }), field_filter),
         )
         .await;
-    }));
+    });
+    track_search(&async_task_tracker, search_id, handler).await;
 
-    Ok(())
+    Ok(search_id)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn search_local_logs(
+    app_handle: AppHandle,
+    query: String,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    apps: Vec<String>,
+    search_id: Option<uuid::Uuid>,
+    async_task_tracker: tauri::State<'_, AsyncTaskManager>,
+    user_config: tauri::State<'_, UserConfigState>,
+    aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+    wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+    log_index: tauri::State<'_, LogIndexInstance>,
+) -> Result<uuid::Uuid, CommandError> {
+    let logs_dir = user_config.0.lock().await.logs_dir.clone();
+    let sources = log_sources(
+        None,
+        &user_config,
+        &aws_config_provider,
+        &wombat_api_instance,
+    )
+    .await;
+    let log_index = log_index
+        .0
+        .clone()
+        .map_err(|msg| CommandError::new("search_local_logs", msg))?;
+
+    let search_id = search_id.unwrap_or_else(uuid::Uuid::new_v4);
+    let emitter = log_events::SearchEmitter::new(app_handle, search_id);
+    let handler = tokio::task::spawn(async move {
+        let mut monitor = WindowNotifier { emitter };
+        monitor.message(String::from("Indexing log dumps..."));
+        let result = tokio::task::spawn_blocking(move || {
+            let ingest = log_index.ingest_dir(&logs_dir, &sources.naming)?;
+            log_index
+                .search(&query, start_timestamp, end_timestamp, &apps)
+                .map(|logs| (logs, ingest.failed))
+        })
+        .await;
+        match result {
+            Ok(Ok((logs, failed))) => {
+                let log_count = logs.len();
+                for chunk in logs.chunks(500) {
+                    monitor.notify(chunk.to_vec());
+                }
+                let found = if log_count >= log_index::SEARCH_RESULT_LIMIT {
+                    format!(
+                        "Found first {log_count} logs in local dumps, narrow the query to see more."
+                    )
+                } else {
+                    format!("Found {log_count} logs in local dumps.")
+                };
+                if failed.is_empty() {
+                    monitor.success(found);
+                } else {
+                    monitor.success(format!(
+                        "{found} {} dump(s) could not be indexed: {}",
+                        failed.len(),
+                        failed.join("; ")
+                    ));
+                }
+            }
+            Ok(Err(e)) => monitor.error(format!("Local search failed, {e}")),
+            Err(e) => monitor.error(format!("Local search failed, {e}")),
+        }
+    });
+    track_search(&async_task_tracker, search_id, handler).await;
+
+    Ok(search_id)
 }
 
 #[tauri::command]
 async fn abort_find_logs(
     reason: String,
+    search_id: Option<uuid::Uuid>,
     async_task_tracker: tauri::State<'_, AsyncTaskManager>,
 ) -> Result<(), CommandError> {
-    info!("Attempt to abort find logs: {}", &reason);
+    info!("Attempt to abort find logs {search_id:?}: {}", &reason);
     let mut tracker = async_task_tracker.0.lock().await;
-    if let Some(handler) = &tracker.search_log_handler {
-        handler.abort();
+    // Without an id every running search is aborted.
+    let aborted: Vec<uuid::Uuid> = match search_id {
+        Some(search_id) => vec![search_id],
+        None => tracker.search_log_handlers.keys().copied().collect(),
+    };
+    for search_id in aborted {
+        if let Some(handler) = tracker.search_log_handlers.remove(&search_id) {
+            handler.abort();
+        }
     }
-    tracker.search_log_handler = None;
 
     Ok(())
 }
@@ -1010,6 +1649,13 @@ async fn start_db_proxy(
         if let Ok(port) = proxy_started {
             let mut bastion_failure_map_inner = bastion_failure_map.0.write().await;
             bastion_failure_map_inner.remove(&bastion.instance_id.clone());
+            let _ = app_handle.emit(
+                "proxy-started",
+                ProxyStarted {
+                    arn: db.arn.clone(),
+                    port,
+                },
+            );
             return Ok(NewTaskParams {
                 port,
                 proxy_auth_config: None,
@@ -1037,7 +1683,7 @@ async fn start_service_proxy(
     infra_profile: Option<InfraProfile>,
     sso_profile: Option<SsoProfile>,
     headers: HashMap<String, String>,
-    proxy_auth_config: Option<wombat_api::ProxyAuthConfig>,
+    proxy_auth_configs: Vec<wombat_api::ProxyAuthConfig>,
     user_config: tauri::State<'_, UserConfigState>,
     app_state: tauri::State<'_, AppContextState>,
     async_task_tracker: tauri::State<'_, AsyncTaskManager>,
@@ -1072,56 +1718,79 @@ async fn start_service_proxy(
         a_failures.cmp(b_failures)
     });
 
-    let mut interceptors: Vec<Box<dyn proxy::ProxyInterceptor>> =
+    let interceptors: Vec<Box<dyn proxy::ProxyInterceptor>> =
         vec![Box::new(proxy::StaticHeadersInterceptor {
             path_prefix: String::new(),
             headers,
         })];
 
-    if let Some(proxy_auth_config) = proxy_auth_config.as_ref() {
+    let host = format!("{}.service", service.name);
+    let mut upstream_client = None;
+    let mut auth_interceptors = Vec::new();
+    if !proxy_auth_configs.is_empty() {
         let (source_app_profile, source_app_config) = aws_config_provider
             .with_dev_way_check(&infra_profile, &sso_profile)
             .await
-            .expect("Missing sdk_config to setup auth interceptor");
-        match proxy_auth_config.auth_type.as_str() {
-            "jepsen" => {
-                info!(
-                    "Adding jepsen auth interceptor, profile={}",
-                    &source_app_profile
-                );
-                interceptors.push(Box::new(
-                    proxy_authenticators::JepsenAutheticator::from_proxy_auth_config(
-                        &source_app_config,
-                        proxy_auth_config.clone(),
-                    ),
-                ));
-            }
-            "basic" => {
-                info!(
-                    "Adding basic auth interceptor, profile={}",
-                    &source_app_profile
-                );
-                interceptors.push(Box::new(
-                    proxy_authenticators::BasicAuthenticator::from_proxy_auth_config(
-                        &source_app_config,
-                        proxy_auth_config.clone(),
-                    )
-                    .await,
-                ));
-            }
-            _ => {
-                warn!("Unknown proxy auth type: {}", proxy_auth_config.auth_type);
-            }
+            .ok_or(CommandError::new(
+                "start_service_proxy",
+                "Missing sdk_config to setup auth interceptor",
+            ))?;
+        let (mtls_configs, path_configs): (Vec<_>, Vec<_>) = proxy_auth_configs
+            .into_iter()
+            .partition(|config| matches!(config.auth, wombat_api::ProxyAuth::Mtls { .. }));
+        if mtls_configs.len() > 1 {
+            return Err(CommandError::new(
+                "start_service_proxy",
+                "Only one mtls config can be used per proxy",
+            ));
         }
+        if let Some(mtls_config) = mtls_configs.first() {
+            info!(
+                "Adding mtls upstream client, profile={}",
+                &source_app_profile
+            );
+            let client = proxy_authenticators::mtls_client(
+                &source_app_config,
+                mtls_config,
+                &host,
+                aws_local_port,
+            )
+            .await
+            .map_err(|msg| CommandError::new("start_service_proxy", msg))?;
+            upstream_client = Some(client);
+        }
+        for proxy_auth_config in path_configs {
+            info!(
+                "Adding {} auth interceptor for {}, profile={}",
+                proxy_auth_config.auth.auth_type(),
+                &proxy_auth_config.api_path,
+                &source_app_profile
+            );
+            let auth_interceptor =
+                proxy_authenticators::auth_interceptor(&source_app_config, proxy_auth_config)
+                    .await
+                    .map_err(|msg| CommandError::new("start_service_proxy", msg))?;
+            auth_interceptors.push(auth_interceptor);
+        }
+    }
+    // mTLS needs https end to end, so the tunnel goes to the TLS port of the service.
+    let (target_port, upstream_address) = if upstream_client.is_some() {
+        (443, format!("https://{host}:{aws_local_port}/"))
+    } else {
+        (80, format!("http://localhost:{aws_local_port}/"))
+    };
+    let mut request_handler = proxy::RequestHandler::new(interceptors);
+    request_handler.upstream_client = upstream_client;
+    for auth_interceptor in auth_interceptors {
+        request_handler.add_auth_interceptor(auth_interceptor);
     }
-    let request_handler = Arc::new(RwLock::new(proxy::RequestHandler { interceptors }));
+    let request_handler = Arc::new(RwLock::new(request_handler));
 
     let region = aws_config_provider.get_region(&aws_profile).await;
-    let host = format!("{}.service", service.name);
     for bastion in bastions.iter().filter(|b| b.env == service.env) {
         let handle = proxy::start_proxy_to_adress(
             local_port,
-            format!("http://localhost:{aws_local_port}/").to_owned(),
+            upstream_address.clone(),
             Arc::clone(&request_handler),
         );
         let proxy_started = proxy::start_aws_ssm_proxy(
@@ -1131,7 +1800,7 @@ async fn start_service_proxy(
             aws_profile.clone(),
             region.clone(),
             host.clone(),
-            80,
+            target_port,
             aws_local_port,
             Some(handle),
             local_port,
@@ -1148,6 +1817,19 @@ async fn start_service_proxy(
         if let Ok(port) = proxy_started {
             let mut bastion_failure_map_inner = bastion_failure_map.0.write().await;
             bastion_failure_map_inner.remove(&bastion.instance_id.clone());
+            async_task_tracker
+                .0
+                .lock()
+                .await
+                .request_handlers
+                .insert(service.arn.clone(), Arc::clone(&request_handler));
+            let _ = app_handle.emit(
+                "proxy-started",
+                ProxyStarted {
+                    arn: service.arn.clone(),
+                    port,
+                },
+            );
             return Ok(NewTaskParams {
                 port,
                 proxy_auth_config: None,
@@ -1167,8 +1849,97 @@ async fn start_service_proxy(
     ))
 }
 
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn add_proxy_auth_config(
+    app_handle: AppHandle,
+    arn: String,
+    infra_profile: Option<InfraProfile>,
+    sso_profile: Option<SsoProfile>,
+    proxy_auth_config: wombat_api::ProxyAuthConfig,
+    app_state: tauri::State<'_, AppContextState>,
+    async_task_tracker: tauri::State<'_, AsyncTaskManager>,
+    aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+) -> Result<(), CommandError> {
+    if let Err(msg) = get_authorized(&app_handle, &app_state.0).await {
+        return Err(CommandError::new("add_proxy_auth_config", msg));
+    }
+    let request_handler = async_task_tracker
+        .0
+        .lock()
+        .await
+        .request_handlers
+        .get(&arn)
+        .cloned()
+        .ok_or(CommandError::new(
+            "add_proxy_auth_config",
+            format!("No running proxy for {arn}"),
+        ))?;
+
+    let aws_config_provider = aws_config_provider.0.read().await;
+    let (source_app_profile, source_app_config) = aws_config_provider
+        .with_dev_way_check(&infra_profile, &sso_profile)
+        .await
+        .ok_or(CommandError::new(
+            "add_proxy_auth_config",
+            "Missing sdk_config to setup auth interceptor",
+        ))?;
+    info!(
+        "Adding {} auth interceptor for {} to running proxy {arn}, profile={}",
+        proxy_auth_config.auth.auth_type(),
+        &proxy_auth_config.api_path,
+        &source_app_profile
+    );
+    let auth_interceptor =
+        proxy_authenticators::auth_interceptor(&source_app_config, proxy_auth_config)
+            .await
+            .map_err(|msg| CommandError::new("add_proxy_auth_config", msg))?;
+    request_handler
+        .write()
+        .await
+        .add_auth_interceptor(auth_interceptor);
+    Ok(())
+}
+
+#[tauri::command]
+async fn remove_proxy_auth_config(
+    app_handle: AppHandle,
+    arn: String,
+    config_id: i64,
+    app_state: tauri::State<'_, AppContextState>,
+    async_task_tracker: tauri::State<'_, AsyncTaskManager>,
+) -> Result<(), CommandError> {
+    if let Err(msg) = get_authorized(&app_handle, &app_state.0).await {
+        return Err(CommandError::new("remove_proxy_auth_config", msg));
+    }
+    let request_handler = async_task_tracker
+        .0
+        .lock()
+        .await
+        .request_handlers
+        .get(&arn)
+        .cloned()
+        .ok_or(CommandError::new(
+            "remove_proxy_auth_config",
+            format!("No running proxy for {arn}"),
+        ))?;
+    if request_handler
+        .write()
+        .await
+        .remove_auth_interceptor(config_id)
+    {
+        Ok(())
+    } else {
+        Err(CommandError::new(
+            "remove_proxy_auth_config",
+            format!("Auth config {config_id} is not used by {arn}"),
+        ))
+    }
+}
+
 #[tauri::command]
 async fn start_cookie_session_proxy(
+    app_handle: AppHandle,
     address: String,
     env: Env,
     headers: HashMap<String, String>,
@@ -1191,13 +1962,14 @@ async fn start_cookie_session_proxy(
 
     interceptors.push(Box::new(proxy_authenticators::CookieAutheticator {
         env: env.clone(),
+        address: address.clone(),
         jar: cookie_jar.0.clone(),
     }));
 
     let handle = proxy::start_proxy_to_adress(
         local_port,
         address.clone(),
-        Arc::new(RwLock::new(proxy::RequestHandler { interceptors })),
+        Arc::new(RwLock::new(proxy::RequestHandler::new(interceptors))),
     );
 
     async_task_tracker
@@ -1206,6 +1978,13 @@ async fn start_cookie_session_proxy(
         .await
         .task_handlers
         .insert(cookie_session_proxy.clone(), handle);
+    let _ = app_handle.emit(
+        "proxy-started",
+        ProxyStarted {
+            arn: cookie_session_proxy.clone(),
+            port: local_port,
+        },
+    );
 
     info!(
         "Started cookie session proxy with id={cookie_session_proxy} with cookie from env={env} to {}",
@@ -1221,6 +2000,7 @@ async fn start_cookie_session_proxy(
 #[allow(clippy::too_many_arguments)]
 #[tauri::command]
 async fn start_lambda_app_proxy(
+    app_handle: AppHandle,
     app: String,
     env: shared::Env,
     address: String,
@@ -1244,13 +2024,14 @@ async fn start_lambda_app_proxy(
 
     interceptors.push(Box::new(proxy_authenticators::CookieAutheticator {
         env,
+        address: address.clone(),
         jar: cookie_jar.0.clone(),
     }));
 
     let handle = proxy::start_proxy_to_adress(
         local_port,
         address.clone(),
-        Arc::new(RwLock::new(proxy::RequestHandler { interceptors })),
+        Arc::new(RwLock::new(proxy::RequestHandler::new(interceptors))),
     );
 
     async_task_tracker
@@ -1259,6 +2040,13 @@ async fn start_lambda_app_proxy(
         .await
         .task_handlers
         .insert(lambda_arn.clone(), handle);
+    let _ = app_handle.emit(
+        "proxy-started",
+        ProxyStarted {
+            arn: lambda_arn.clone(),
+            port: local_port,
+        },
+    );
 
     info!("Started lambda proxy={} to {}", lambda_arn, &address);
 
@@ -1271,20 +2059,74 @@ async fn start_lambda_app_proxy(
 #[tauri::command]
 async fn log_filters(
     wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+    local_log_filters: tauri::State<'_, LocalLogFiltersInstance>,
 ) -> Result<Vec<wombat_api::LogFilter>, CommandError> {
     let mut wombat_api = wombat_api_instance.0.lock().await;
-    let filters = wombat_api.log_filters().await;
+    let mut filters = wombat_api.log_filters().await;
+    match local_log_filters.0.list() {
+        Ok(local) => filters.extend(local),
+        Err(e) => warn!("Failed to read local log filters, {e}"),
+    }
     Ok(filters)
 }
 
 #[tauri::command]
-async fn proxy_auth_configs(
+async fn create_log_filter(
+    filter: log_filters::LogFilterInput,
+    local_log_filters: tauri::State<'_, LocalLogFiltersInstance>,
+) -> Result<wombat_api::LogFilter, CommandError> {
+    local_log_filters
+        .0
+        .create(filter)
+        .map_err(|e| CommandError::new("create_log_filter", e))
+}
+
+#[tauri::command]
+async fn update_log_filter(
+    id: i64,
+    filter: log_filters::LogFilterInput,
+    local_log_filters: tauri::State<'_, LocalLogFiltersInstance>,
+) -> Result<wombat_api::LogFilter, CommandError> {
+    local_log_filters
+        .0
+        .update(id, filter)
+        .map_err(|e| CommandError::new("update_log_filter", e))
+}
+
+#[tauri::command]
+async fn delete_log_filter(
+    id: i64,
+    local_log_filters: tauri::State<'_, LocalLogFiltersInstance>,
+) -> Result<(), CommandError> {
+    local_log_filters
+        .0
+        .delete(id)
+        .map_err(|e| CommandError::new("delete_log_filter", e))
+}
+
+#[tauri::command]
+async fn propose_log_filter(
+    id: i64,
     wombat_api_instance: tauri::State<'_, WombatApiInstance>,
-) -> Result<Vec<wombat_api::ProxyAuthConfig>, CommandError> {
+    local_log_filters: tauri::State<'_, LocalLogFiltersInstance>,
+) -> Result<(), CommandError> {
+    let log_filter = local_log_filters
+        .0
+        .get(id)
+        .map_err(|e| CommandError::new("propose_log_filter", e))?;
     let mut wombat_api = wombat_api_instance.0.lock().await;
-    let configs = wombat_api.get_proxy_auth_configs().await;
+    wombat_api
+        .propose_log_filter(&log_filter)
+        .await
+        .map_err(|e| CommandError::new("propose_log_filter", e))
+}
 
-    Ok(configs)
+#[tauri::command]
+async fn proxy_auth_configs(
+    wombat_api_instance: tauri::State<'_, WombatApiInstance>,
+) -> Result<wombat_api::ProxyAuthConfigs, CommandError> {
+    let mut wombat_api = wombat_api_instance.0.lock().await;
+    wombat_api.get_proxy_auth_configs().await
 }
 
 #[tauri::command]
@@ -1310,12 +2152,14 @@ async fn all_features_enabled(
 async fn check_dependencies(
     wombat_api_instance: tauri::State<'_, WombatApiInstance>,
     aws_config_provider: tauri::State<'_, AwsConfigProviderInstance>,
+    rest_api_status: tauri::State<'_, RestApiStatusInstance>,
 ) -> Result<Vec<dependency_check::Dependency>, ()> {
     let mut wombat_api = wombat_api_instance.0.lock().await;
     let aws_config_provider = aws_config_provider.0.read().await;
     Ok(dependency_check::check_dependencies(
         &mut wombat_api,
         &aws_config_provider,
+        &rest_api_status.0,
         requirements::REQUIRED_FEATURE,
     )
     .await)
@@ -1633,16 +2477,18 @@ async fn main() {
         let mut wombat_api_ref_clone = wombat_api_ref_clone.lock().await;
         wombat_api_ref_clone.auth().await;
     });
-    rest_api::serve(
-        cookie_jar.clone(),
-        browser_ext.clone(),
-        Arc::new(Mutex::new(wombat_api::WombatApi::new(
-            app_config.wombat_api_url.clone(),
-            app_config.wombat_api_user.clone(),
-            app_config.wombat_api_password.clone(),
-            user.id,
-        ))),
-    );
+    let rest_api_auth = Arc::new(Mutex::new(rest_api_auth::RestApiAuth::load()));
+    let rest_api_cookie_jar = cookie_jar.clone();
+    let rest_api_browser_ext = browser_ext.clone();
+    let rest_api_wombat_api = Arc::new(Mutex::new(wombat_api::WombatApi::new(
+        app_config.wombat_api_url.clone(),
+        app_config.wombat_api_user.clone(),
+        app_config.wombat_api_password.clone(),
+        user.id,
+    )));
+    let rest_api_auth_ref = rest_api_auth.clone();
+    let rest_api_port = user.rest_api_port;
+    let event_stream = event_stream::EventStream::default();
 
     let _guard = match app_config.logger.as_str() {
         "console" => {
@@ -1671,10 +2517,23 @@ async fn main() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_notification::init())
+        .setup(move |app| {
             app.handle()
                 .plugin(tauri_plugin_updater::Builder::new().build())
                 .expect("Failed to initialize updater plugin");
+            event_stream.forward_app_events(app.handle());
+            event_stream::watch_cookie_health(app.handle().clone(), rest_api_cookie_jar.clone());
+            let rest_api_status = rest_api::serve(
+                rest_api_port,
+                rest_api_cookie_jar,
+                rest_api_browser_ext,
+                rest_api_wombat_api,
+                rest_api_auth_ref,
+                app.handle().clone(),
+                event_stream,
+            );
+            app.manage(RestApiStatusInstance(rest_api_status));
             Ok(())
         })
         .manage(AppContextState(Arc::new(Mutex::new(AppContext {
@@ -1685,12 +2544,15 @@ async fn main() {
         }))))
         .manage(BrowserExtensionInstance(browser_ext))
         .manage(CookieJarInstance(cookie_jar))
+        .manage(RestApiAuthInstance(rest_api_auth))
         .manage(UserConfigState(Arc::new(Mutex::new(user))))
         .manage(AsyncTaskManager(Arc::new(Mutex::new(TaskTracker {
             aws_resource_refresher: None,
             proxies_handlers: HashMap::new(),
             task_handlers: HashMap::new(),
-            search_log_handler: None,
+            request_handlers: HashMap::new(),
+            search_log_handlers: HashMap::new(),
+            log_watch_scheduler: None,
         }))))
         .manage(AwsConfigProviderInstance(aws_config_provider.clone()))
         .manage(RdsResolverInstance(Arc::new(RwLock::new(
@@ -1707,11 +2569,20 @@ async fn main() {
             store: HashMap::new(),
         }))))
         .manage(BastionFailureMap(Arc::new(RwLock::new(HashMap::new()))))
+        .manage(LogIndexInstance(
+            log_index::LogIndex::open()
+                .inspect_err(|e| error!("{e}"))
+                .map(Arc::new),
+        ))
+        .manage(LocalLogFiltersInstance(Arc::new(
+            log_filters::LocalLogFilters::open(),
+        )))
         .invoke_handler(tauri::generate_handler![
             user_config,
             reload_aws_config,
             set_dbeaver_path,
             set_logs_dir_path,
+            set_rest_api_port,
             save_preferred_envs,
             login,
             logout,
@@ -1724,14 +2595,31 @@ async fn main() {
             favorite,
             start_db_proxy,
             start_service_proxy,
+            add_proxy_auth_config,
+            remove_proxy_auth_config,
             open_dbeaver,
             discover,
             refresh_cache,
             credentials,
             stop_job,
             find_logs,
+            find_logs_across_envs,
+            trace_request,
+            search_local_logs,
+            resume_find_logs,
+            log_search_checkpoints,
+            log_watches,
+            save_log_watch,
+            delete_log_watch,
+            log_naming,
+            set_log_naming,
+            tail_logs,
             abort_find_logs,
             log_filters,
+            create_log_filter,
+            update_log_filter,
+            delete_log_filter,
+            propose_log_filter,
             proxy_auth_configs,
             is_feature_enabled,
             all_features_enabled,
@@ -1744,6 +2632,9 @@ async fn main() {
             start_cookie_session_proxy,
             browser_extension_health,
             cookie_jar_status,
+            rest_api_pairing_code,
+            rest_api_clients,
+            revoke_rest_api_client,
             codeartifact_login,
             codeartifact_login_check,
             is_debug,
@@ -1753,7 +2644,11 @@ async fn main() {
         ])
         .build(tauri::generate_context!())
         .expect("Error while running tauri application");
-    app.run(|_handle, _event| {});
+    app.run(|_handle, event| {
+        if let tauri::RunEvent::Exit = event {
+            rest_api::remove_discovery_file();
+        }
+    });
 }
 
 struct AppContext {
@@ -1777,7 +2672,12 @@ struct EcsResolverInstance(Arc<RwLock<EcsResolver>>);
 struct WombatApiInstance(Arc<Mutex<wombat_api::WombatApi>>);
 struct BrowserExtensionInstance(Arc<Mutex<BrowserExtension>>);
 struct CookieJarInstance(Arc<Mutex<CookieJar>>);
+struct RestApiAuthInstance(Arc<Mutex<rest_api_auth::RestApiAuth>>);
+struct RestApiStatusInstance(Result<u16, String>);
 struct KVStoreInstance(Arc<Mutex<KVStore>>);
+struct LogIndexInstance(Result<Arc<log_index::LogIndex>, String>);
+struct LocalLogFiltersInstance(Arc<log_filters::LocalLogFilters>);
+
 struct BastionFailureMap(Arc<RwLock<HashMap<String, isize>>>);
 
 struct KVStore {
@@ -1803,7 +2703,9 @@ struct TaskTracker {
     aws_resource_refresher: Option<tokio::task::JoinHandle<()>>,
     proxies_handlers: HashMap<String, Arc<SharedChild>>,
     task_handlers: HashMap<String, tokio::sync::oneshot::Sender<()>>,
-    search_log_handler: Option<tokio::task::JoinHandle<()>>,
+    request_handlers: HashMap<String, Arc<RwLock<proxy::RequestHandler>>>,
+    search_log_handlers: HashMap<uuid::Uuid, tokio::task::JoinHandle<()>>,
+    log_watch_scheduler: Option<tokio::task::JoinHandle<()>>,
 }
 
 async fn check_login_and_trigger(
@@ -17,6 +17,7 @@ use warp::http::{HeaderName, HeaderValue};
 use warp::hyper::body::Bytes;
 use warp::hyper::Method;
 use warp::Filter as WarpFilter;
+use warp::Reply;
 use warp_reverse_proxy::{extract_request_data_filter, proxy_to_and_forward_response, Headers};
 
 #[allow(clippy::too_many_arguments)]
@@ -138,7 +139,8 @@ pub async fn start_aws_ssm_proxy(
 #[async_trait]
 pub trait ProxyInterceptor: Send + Sync {
     fn applies(&self, uri: &str) -> bool;
-    async fn modify_headers(&self, headers: &mut Headers);
+    async fn modify_headers(&self, uri: &str, headers: &mut Headers);
+    async fn inspect_response(&self, _uri: &str, _headers: &Headers) {}
 }
 
 #[derive(Clone)]
@@ -152,7 +154,7 @@ impl ProxyInterceptor for StaticHeadersInterceptor {
     fn applies(&self, uri: &str) -> bool {
         uri.starts_with(&self.path_prefix)
     }
-    async fn modify_headers(&self, headers: &mut Headers) {
+    async fn modify_headers(&self, _uri: &str, headers: &mut Headers) {
         let h = self.headers.clone();
         headers.remove("Host");
         headers.remove("Content-Length");
@@ -164,23 +166,137 @@ impl ProxyInterceptor for StaticHeadersInterceptor {
     }
 }
 
+pub struct AuthInterceptor {
+    pub config_id: i64,
+    pub path_prefix: String,
+    pub interceptor: Box<dyn ProxyInterceptor>,
+}
+
 pub struct RequestHandler {
     pub interceptors: Vec<Box<dyn ProxyInterceptor>>,
+    pub auth_interceptors: Vec<AuthInterceptor>,
+    pub upstream_client: Option<reqwest::Client>,
+}
+
+impl RequestHandler {
+    pub fn new(interceptors: Vec<Box<dyn ProxyInterceptor>>) -> Self {
+        RequestHandler {
+            interceptors,
+            auth_interceptors: Vec::new(),
+            upstream_client: None,
+        }
+    }
+
+    pub fn add_auth_interceptor(&mut self, auth_interceptor: AuthInterceptor) {
+        info!(
+            "Adding auth interceptor, config_id={}, path_prefix={}",
+            auth_interceptor.config_id, &auth_interceptor.path_prefix
+        );
+        self.auth_interceptors
+            .retain(|existing| existing.config_id != auth_interceptor.config_id);
+        self.auth_interceptors.push(auth_interceptor);
+    }
+
+    pub fn remove_auth_interceptor(&mut self, config_id: i64) -> bool {
+        let count = self.auth_interceptors.len();
+        self.auth_interceptors
+            .retain(|existing| existing.config_id != config_id);
+        count != self.auth_interceptors.len()
+    }
+
+    fn auth_interceptor_for(&self, uri: &str) -> Option<&AuthInterceptor> {
+        self.auth_interceptors
+            .iter()
+            .filter(|auth_interceptor| uri.starts_with(&auth_interceptor.path_prefix))
+            .max_by_key(|auth_interceptor| auth_interceptor.path_prefix.len())
+    }
 }
 
 async fn handle(
     uri: &str,
     headers: &mut Headers,
     handler: Arc<tokio::sync::RwLock<RequestHandler>>,
-) {
+) -> Option<reqwest::Client> {
     info!("Handling request, {}", &uri);
     let handler = handler.read().await;
     let interceptors_ref = &handler.interceptors;
     for interceptor in interceptors_ref.iter() {
         if interceptor.applies(uri) {
-            interceptor.modify_headers(headers).await;
+            interceptor.modify_headers(uri, headers).await;
+        }
+    }
+    if let Some(auth_interceptor) = handler.auth_interceptor_for(uri) {
+        info!(
+            "Using auth interceptor, config_id={}",
+            auth_interceptor.config_id
+        );
+        auth_interceptor
+            .interceptor
+            .modify_headers(uri, headers)
+            .await;
+    }
+    handler.upstream_client.clone()
+}
+
+async fn inspect_response(
+    uri: &str,
+    headers: &Headers,
+    handler: Arc<tokio::sync::RwLock<RequestHandler>>,
+) {
+    let handler = handler.read().await;
+    for interceptor in handler.interceptors.iter() {
+        if interceptor.applies(uri) {
+            interceptor.inspect_response(uri, headers).await;
         }
     }
+    if let Some(auth_interceptor) = handler.auth_interceptor_for(uri) {
+        auth_interceptor
+            .interceptor
+            .inspect_response(uri, headers)
+            .await;
+    }
+}
+
+#[derive(Debug)]
+struct UpstreamError(String);
+impl warp::reject::Reject for UpstreamError {}
+
+async fn proxy_with_client(
+    client: reqwest::Client,
+    address: String,
+    uri: warp::path::FullPath,
+    params: Option<String>,
+    method: Method,
+    headers: Headers,
+    body: Bytes,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let mut url = format!("{}{}", address.trim_end_matches('/'), uri.as_str());
+    if let Some(params) = params {
+        url = format!("{url}?{params}");
+    }
+    let response = client
+        .request(method, &url)
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| {
+            warn!("Upstream request to {url} failed, {e}");
+            warp::reject::custom(UpstreamError(e.to_string()))
+        })?;
+
+    let mut builder = warp::http::Response::builder().status(response.status());
+    for (name, value) in response.headers() {
+        builder = builder.header(name, value);
+    }
+    let response_body = response
+        .bytes()
+        .await
+        .map_err(|e| warp::reject::custom(UpstreamError(e.to_string())))?;
+    builder
+        .body(response_body)
+        .map(|response| response.into_response())
+        .map_err(|e| warp::reject::custom(UpstreamError(e.to_string())))
 }
 
 pub fn start_proxy_to_adress(
@@ -203,17 +319,28 @@ pub fn start_proxy_to_adress(
                 let request_handler = request_handler.clone();
                 let address = address.clone();
                 async move {
-                    handle(uri.as_str(), &mut headers, request_handler).await;
-                    proxy_to_and_forward_response(
-                        address,
-                        "".to_owned(),
-                        uri,
-                        params,
-                        method,
-                        headers,
-                        body,
-                    )
-                    .await
+                    let upstream_client =
+                        handle(uri.as_str(), &mut headers, request_handler.clone()).await;
+                    let path = uri.as_str().to_owned();
+                    let response = match upstream_client {
+                        Some(client) => {
+                            proxy_with_client(client, address, uri, params, method, headers, body)
+                                .await
+                        }
+                        None => proxy_to_and_forward_response(
+                            address,
+                            "".to_owned(),
+                            uri,
+                            params,
+                            method,
+                            headers,
+                            body,
+                        )
+                        .await
+                        .map(|response| response.into_response()),
+                    }?;
+                    inspect_response(&path, response.headers(), request_handler).await;
+                    Ok::<_, warp::Rejection>(response)
                 }
             },
         )
@@ -1,11 +1,14 @@
-use crate::proxy::ProxyInterceptor;
-use crate::shared::Env;
-use crate::{aws, shared::CookieJar, wombat_api};
+use crate::proxy::{AuthInterceptor, ProxyInterceptor};
+use crate::shared::{domain_matches, normalize_cookie_domain, Cookie, Env};
+use crate::wombat_api::{ProxyAuth, ProxyAuthConfig};
+use crate::{aws, shared::CookieJar};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use headers::authorization::Credentials;
 use headers::Authorization;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
 use log::{info, warn};
-use tracing_unwrap::ResultExt;
+use std::str::FromStr;
 use warp_reverse_proxy::Headers;
 
 #[derive(serde::Serialize)]
@@ -44,17 +47,21 @@ pub struct JepsenAutheticator {
     secret_arn: String,
 }
 impl JepsenAutheticator {
-    pub fn from_proxy_auth_config(
+    pub fn new(
         aws_config: &aws_config::SdkConfig,
-        jepsen_config: wombat_api::ProxyAuthConfig,
+        path_prefix: String,
+        jepsen_url: String,
+        api_name: String,
+        client_id: String,
+        secret_arn: String,
     ) -> Self {
         JepsenAutheticator {
             aws_config: aws_config.clone(),
-            api_name: jepsen_config.jepsen_api_name.unwrap(),
-            jepsen_url: jepsen_config.jepsen_auth_api.unwrap(),
-            path_prefix: jepsen_config.api_path,
-            client_id: jepsen_config.jepsen_client_id.unwrap(),
-            secret_arn: jepsen_config.secret_name,
+            api_name,
+            jepsen_url,
+            path_prefix,
+            client_id,
+            secret_arn,
         }
     }
 
@@ -62,7 +69,7 @@ impl JepsenAutheticator {
         info!("Getting token {}", &self.secret_arn);
         let client_secret = aws::get_secret(&self.aws_config, &self.secret_arn)
             .await
-            .unwrap_or_log();
+            .map_err(|e| format!("Failed to get jepsen client secret, {}", e.message))?;
         let client = reqwest::Client::new();
         let response = client
             .post(&self.jepsen_url)
@@ -97,7 +104,7 @@ impl ProxyInterceptor for JepsenAutheticator {
     fn applies(&self, uri: &str) -> bool {
         uri.starts_with(&self.path_prefix)
     }
-    async fn modify_headers(&self, headers: &mut Headers) {
+    async fn modify_headers(&self, _uri: &str, headers: &mut Headers) {
         if let Ok(token) = self.get_jepsen_token().await {
             info!("adding jepsen headers");
             headers.insert(
@@ -114,16 +121,16 @@ pub struct BasicAuthenticator {
     password: Option<String>,
 }
 impl BasicAuthenticator {
-    pub async fn from_proxy_auth_config(
+    pub async fn new(
         aws_config: &aws_config::SdkConfig,
-        basic_config: wombat_api::ProxyAuthConfig,
+        path_prefix: String,
+        user: String,
+        secret_name: &str,
     ) -> Self {
         BasicAuthenticator {
-            user: basic_config.basic_user.unwrap(),
-            path_prefix: basic_config.api_path,
-            password: aws::get_secret(aws_config, basic_config.secret_name.as_str())
-                .await
-                .ok(),
+            user,
+            path_prefix,
+            password: aws::get_secret(aws_config, secret_name).await.ok(),
         }
     }
 }
@@ -134,7 +141,7 @@ impl ProxyInterceptor for BasicAuthenticator {
         uri.starts_with(&self.path_prefix)
     }
 
-    async fn modify_headers(&self, headers: &mut Headers) {
+    async fn modify_headers(&self, _uri: &str, headers: &mut Headers) {
         if let Some(password) = self.password.clone() {
             let credentials = Authorization::basic(&self.user, &password).0.encode();
             let credentials_value = credentials.to_str().unwrap();
@@ -146,22 +153,387 @@ impl ProxyInterceptor for BasicAuthenticator {
     }
 }
 
+const JWT_DEFAULT_TTL_SECONDS: i64 = 15 * 60;
+const JWT_REFRESH_BEFORE_SECONDS: i64 = 30;
+
+pub struct JwtAuthenticator {
+    path_prefix: String,
+    algorithm: Algorithm,
+    encoding_key: Option<EncodingKey>,
+    claims: serde_json::Map<String, serde_json::Value>,
+    ttl: chrono::Duration,
+    per_request: bool,
+    session_token: tokio::sync::Mutex<Option<(String, DateTime<Utc>)>>,
+}
+impl JwtAuthenticator {
+    pub async fn new(
+        aws_config: &aws_config::SdkConfig,
+        path_prefix: String,
+        algorithm: Option<&str>,
+        claims: Option<serde_json::Value>,
+        ttl_seconds: Option<i64>,
+        per_request: Option<bool>,
+        secret_name: &str,
+    ) -> Result<Self, String> {
+        let algorithm = algorithm
+            .map(Algorithm::from_str)
+            .unwrap_or(Ok(Algorithm::HS256))
+            .map_err(|e| format!("Unsupported jwt algorithm, {e}"))?;
+        let encoding_key = match aws::get_secret(aws_config, secret_name).await {
+            Ok(key) => Self::encoding_key(algorithm, &key),
+            Err(e) => {
+                warn!("Failed to get jwt signing key, {}", e.message);
+                None
+            }
+        };
+        let claims = match claims {
+            Some(serde_json::Value::Object(claims)) => claims,
+            None => serde_json::Map::new(),
+            Some(_) => return Err("Jwt claims must be an object".to_owned()),
+        };
+
+        Ok(JwtAuthenticator {
+            path_prefix,
+            algorithm,
+            encoding_key,
+            claims,
+            ttl: chrono::Duration::seconds(ttl_seconds.unwrap_or(JWT_DEFAULT_TTL_SECONDS)),
+            per_request: per_request.unwrap_or(false),
+            session_token: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    fn encoding_key(algorithm: Algorithm, key: &str) -> Option<EncodingKey> {
+        let encoding_key = match algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                Ok(EncodingKey::from_secret(key.as_bytes()))
+            }
+            Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(key.as_bytes()),
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(key.as_bytes()),
+            _ => EncodingKey::from_rsa_pem(key.as_bytes()),
+        };
+        match encoding_key {
+            Ok(encoding_key) => Some(encoding_key),
+            Err(e) => {
+                warn!("Failed to parse jwt signing key, {e}");
+                None
+            }
+        }
+    }
+
+    fn mint_token(&self, encoding_key: &EncodingKey) -> Result<(String, DateTime<Utc>), String> {
+        let now = Utc::now();
+        let expires_at = now + self.ttl;
+        let mut claims = self.claims.clone();
+        claims.insert("iat".to_owned(), now.timestamp().into());
+        claims.insert("exp".to_owned(), expires_at.timestamp().into());
+        claims.insert("jti".to_owned(), uuid::Uuid::new_v4().to_string().into());
+
+        jsonwebtoken::encode(&Header::new(self.algorithm), &claims, encoding_key)
+            .map(|token| (token, expires_at))
+            .map_err(|e| format!("Failed to mint jwt, {e}"))
+    }
+
+    pub async fn get_jwt_token(&self) -> Result<String, String> {
+        let encoding_key = self
+            .encoding_key
+            .as_ref()
+            .ok_or("Missing jwt signing key".to_owned())?;
+        if self.per_request {
+            return self.mint_token(encoding_key).map(|(token, _)| token);
+        }
+
+        let mut session_token = self.session_token.lock().await;
+        if let Some((token, expires_at)) = session_token.as_ref() {
+            if (*expires_at - Utc::now()).num_seconds() > JWT_REFRESH_BEFORE_SECONDS {
+                return Ok(token.clone());
+            }
+        }
+        info!("Minting new session jwt");
+        let (token, expires_at) = self.mint_token(encoding_key)?;
+        *session_token = Some((token.clone(), expires_at));
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl ProxyInterceptor for JwtAuthenticator {
+    fn applies(&self, uri: &str) -> bool {
+        uri.starts_with(&self.path_prefix)
+    }
+
+    async fn modify_headers(&self, _uri: &str, headers: &mut Headers) {
+        match self.get_jwt_token().await {
+            Ok(token) => {
+                info!("adding jwt header");
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {}", &token).parse().unwrap(),
+                );
+            }
+            Err(e) => warn!("{e}, skipping jwt header"),
+        }
+    }
+}
+
+pub async fn auth_interceptor(
+    aws_config: &aws_config::SdkConfig,
+    proxy_auth_config: ProxyAuthConfig,
+) -> Result<AuthInterceptor, String> {
+    proxy_auth_config.validate()?;
+    let config_id = proxy_auth_config.id;
+    let path_prefix = proxy_auth_config.api_path;
+    let interceptor: Box<dyn ProxyInterceptor> = match proxy_auth_config.auth {
+        ProxyAuth::Jepsen {
+            jepsen_auth_api,
+            jepsen_api_name,
+            jepsen_client_id,
+            secret_name,
+        } => Box::new(JepsenAutheticator::new(
+            aws_config,
+            path_prefix.clone(),
+            jepsen_auth_api,
+            jepsen_api_name,
+            jepsen_client_id,
+            secret_name,
+        )),
+        ProxyAuth::Basic {
+            basic_user,
+            secret_name,
+        } => Box::new(
+            BasicAuthenticator::new(aws_config, path_prefix.clone(), basic_user, &secret_name)
+                .await,
+        ),
+        ProxyAuth::Jwt {
+            jwt_algorithm,
+            jwt_claims,
+            jwt_ttl_seconds,
+            jwt_per_request,
+            secret_name,
+        } => Box::new(
+            JwtAuthenticator::new(
+                aws_config,
+                path_prefix.clone(),
+                jwt_algorithm.as_deref(),
+                jwt_claims,
+                jwt_ttl_seconds,
+                jwt_per_request,
+                &secret_name,
+            )
+            .await?,
+        ),
+        ProxyAuth::Mtls { .. } => {
+            return Err(
+                "mtls is applied to the upstream connection, not to a path prefix".to_owned(),
+            )
+        }
+    };
+
+    Ok(AuthInterceptor {
+        config_id,
+        path_prefix,
+        interceptor,
+    })
+}
+
+const MTLS_LOCAL_FILE_PREFIX: &str = "file://";
+
+async fn load_mtls_pem(aws_config: &aws_config::SdkConfig, source: &str) -> Result<String, String> {
+    match source.strip_prefix(MTLS_LOCAL_FILE_PREFIX) {
+        Some(path) => {
+            info!("Loading mtls pem from file {path}");
+            tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| format!("Failed to read {path}, {e}"))
+        }
+        None => aws::get_secrets_manager_secret(aws_config, source)
+            .await
+            .map_err(|e| format!("Failed to load {source}, {}", e.message)),
+    }
+}
+
+/// Client presenting the configured certificate to the upstream. `host` is resolved to the
+/// local end of the tunnel, so TLS is verified against the real service name.
+pub async fn mtls_client(
+    aws_config: &aws_config::SdkConfig,
+    mtls_config: &ProxyAuthConfig,
+    host: &str,
+    tunnel_port: u16,
+) -> Result<reqwest::Client, String> {
+    mtls_config.validate()?;
+    let ProxyAuth::Mtls {
+        mtls_cert: cert_source,
+        mtls_key: key_source,
+    } = &mtls_config.auth
+    else {
+        return Err(format!(
+            "Proxy auth config id={} is not an mtls config",
+            mtls_config.id
+        ));
+    };
+
+    let cert = load_mtls_pem(aws_config, cert_source).await?;
+    let key = load_mtls_pem(aws_config, key_source).await?;
+    let identity = reqwest::Identity::from_pkcs8_pem(cert.as_bytes(), key.as_bytes())
+        .map_err(|e| format!("Invalid mtls certificate or key, {e}"))?;
+
+    reqwest::Client::builder()
+        .tls_backend_native()
+        .identity(identity)
+        .resolve(
+            host,
+            std::net::SocketAddr::from(([127, 0, 0, 1], tunnel_port)),
+        )
+        .build()
+        .map_err(|e| format!("Failed to build mtls client, {e}"))
+}
+
 pub struct CookieAutheticator {
     pub env: Env,
+    pub address: String,
     pub jar: std::sync::Arc<tokio::sync::Mutex<CookieJar>>,
 }
 
+impl CookieAutheticator {
+    fn target_url(&self, uri: &str) -> Option<url::Url> {
+        let target = format!("{}{}", self.address.trim_end_matches('/'), uri);
+        url::Url::parse(&target)
+            .inspect_err(|e| warn!("Failed to parse target url for {}, {e}", &self.address))
+            .ok()
+    }
+
+    fn cookie_from_set_cookie(&self, url: &url::Url, set_cookie: &str) -> Option<Cookie> {
+        let parsed = match cookie::Cookie::parse(set_cookie) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("Failed to parse Set-Cookie header, {e}");
+                return None;
+            }
+        };
+        let host = url.host_str()?;
+        // A Domain attribute must cover the request host, RFC 6265 section 5.3 step 6.
+        let (domain, host_only) = match parsed.domain() {
+            Some(domain) if !domain_matches(host, domain) => {
+                warn!(
+                    "Ignoring cookie {} with domain {domain} set by {host}",
+                    parsed.name()
+                );
+                return None;
+            }
+            Some(domain) => (normalize_cookie_domain(domain), false),
+            None => (host.to_lowercase(), true),
+        };
+        let now = Utc::now();
+        let expires_at = match (parsed.max_age(), parsed.expires_datetime()) {
+            (Some(max_age), _) => Some(now + chrono::Duration::seconds(max_age.whole_seconds())),
+            (None, Some(expires)) => DateTime::from_timestamp(expires.unix_timestamp(), 0),
+            (None, None) => None,
+        };
+        let path = match parsed.path() {
+            Some(path) if path.starts_with('/') => path.to_owned(),
+            _ => default_cookie_path(url.path()),
+        };
+        Some(Cookie {
+            name: parsed.name().to_owned(),
+            value: parsed.value().to_owned(),
+            env: self.env.clone(),
+            stored_at: now,
+            domain: Some(domain),
+            path: Some(path),
+            expires_at,
+            secure: parsed.secure().unwrap_or(false),
+            host_only,
+        })
+    }
+}
+
+fn default_cookie_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(idx) => request_path[..idx].to_owned(),
+    }
+}
+
 #[async_trait]
 impl ProxyInterceptor for CookieAutheticator {
     fn applies(&self, _uri: &str) -> bool {
         true
     }
-    async fn modify_headers(&self, headers: &mut Headers) {
-        let jar = self.jar.lock().await;
-        let header_value = jar.header_value_for_env(&self.env);
+    async fn modify_headers(&self, uri: &str, headers: &mut Headers) {
+        let Some(url) = self.target_url(uri) else {
+            return;
+        };
+        let mut jar = self.jar.lock().await;
+        let header_value = jar.header_value_for(&self.env, &url);
         if !header_value.is_empty() {
             info!("Injecting cookie: {header_value}");
             headers.insert("Cookie", header_value.parse().unwrap());
         }
     }
+    async fn inspect_response(&self, uri: &str, headers: &Headers) {
+        let Some(url) = self.target_url(uri) else {
+            return;
+        };
+        let cookies: Vec<Cookie> = headers
+            .get_all(warp::http::header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| self.cookie_from_set_cookie(&url, value))
+            .collect();
+        if cookies.is_empty() {
+            return;
+        }
+        let mut jar = self.jar.lock().await;
+        for cookie in cookies {
+            info!(
+                "Storing cookie from response for env={}, name={}, domain={:?}, path={:?}",
+                &cookie.env, &cookie.name, &cookie.domain, &cookie.path
+            );
+            jar.put(cookie);
+        }
+        jar.evict_expired();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator() -> CookieAutheticator {
+        CookieAutheticator {
+            env: Env::DEV,
+            address: "https://app.example.com".to_owned(),
+            jar: std::sync::Arc::new(tokio::sync::Mutex::new(CookieJar {
+                cookies: Vec::new(),
+            })),
+        }
+    }
+
+    fn parse(set_cookie: &str) -> Option<Cookie> {
+        let authenticator = authenticator();
+        let url = authenticator.target_url("/api/users").unwrap();
+        authenticator.cookie_from_set_cookie(&url, set_cookie)
+    }
+
+    #[test]
+    fn cookie_without_domain_is_host_only() {
+        let cookie = parse("session=abc").unwrap();
+        assert_eq!(cookie.domain.as_deref(), Some("app.example.com"));
+        assert!(cookie.host_only);
+        assert_eq!(cookie.path.as_deref(), Some("/api"));
+    }
+
+    #[test]
+    fn cookie_for_parent_domain_is_kept() {
+        let cookie = parse("session=abc; Domain=.example.com; Path=/").unwrap();
+        assert_eq!(cookie.domain.as_deref(), Some("example.com"));
+        assert!(!cookie.host_only);
+        assert_eq!(cookie.path.as_deref(), Some("/"));
+    }
+
+    #[test]
+    fn cookie_for_foreign_domain_is_rejected() {
+        assert!(parse("session=abc; Domain=other.com").is_none());
+        assert!(parse("session=abc; Domain=api.app.example.com").is_none());
+    }
 }
@@ -1,4 +1,7 @@
-use crate::shared::{BrowserExtension, Cookie, CookieJar, Env};
+use crate::control_api;
+use crate::event_stream::EventStream;
+use crate::rest_api_auth::{self, RestApiAuth};
+use crate::shared::{normalize_cookie_domain, BrowserExtension, Cookie, CookieJar, Env};
 use crate::wombat_api::WombatApi;
 use chrono::{TimeZone, Utc};
 use serde::{Deserialize, Serialize};
@@ -13,6 +16,10 @@ struct NewCookieDto {
     value: String,
     env: Env,
     stored_at: i64,
+    domain: Option<String>,
+    path: Option<String>,
+    expires_at: Option<i64>,
+    secure: Option<bool>,
 }
 impl From<NewCookieDto> for Cookie {
     fn from(dto: NewCookieDto) -> Self {
@@ -21,6 +28,16 @@ impl From<NewCookieDto> for Cookie {
             env: dto.env,
             value: dto.value,
             stored_at: Utc.timestamp_millis_opt(dto.stored_at).unwrap(),
+            domain: dto
+                .domain
+                .map(|domain| normalize_cookie_domain(&domain))
+                .filter(|domain| !domain.is_empty()),
+            path: dto.path.filter(|path| !path.is_empty()),
+            expires_at: dto
+                .expires_at
+                .and_then(|expires_at| Utc.timestamp_millis_opt(expires_at).single()),
+            secure: dto.secure.unwrap_or(false),
+            host_only: false,
         }
     }
 }
@@ -30,14 +47,15 @@ async fn put_cookie(
     jar: std::sync::Arc<tokio::sync::Mutex<CookieJar>>,
 ) -> Result<warp::reply::Response, warp::Rejection> {
     log::info!(
-        "Storing cookie for env={}, name={}, value={}",
+        "Storing cookie for env={}, name={}, value={}, domain={:?}, path={:?}",
         &dto.env,
         &dto.name,
-        &dto.value
+        &dto.value,
+        &dto.domain,
+        &dto.path
     );
     let mut jar = jar.lock().await;
-    jar.cookies.retain(|cookie| cookie.name != dto.name);
-    jar.cookies.push(Cookie::from(dto));
+    jar.put(Cookie::from(dto));
     Ok(warp::reply().into_response())
 }
 
@@ -88,6 +106,127 @@ async fn browser_extension_event(
     Ok(warp::reply().into_response())
 }
 
+fn events(events: EventStream) -> warp::reply::Response {
+    let stream = futures::stream::unfold(events.subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(envelope) => {
+                    match warp::sse::Event::default()
+                        .event(envelope.event.event_type())
+                        .json_data(&envelope)
+                    {
+                        Ok(event) => {
+                            return Some((Ok::<_, std::convert::Infallible>(event), receiver))
+                        }
+                        Err(e) => {
+                            log::warn!(
+                                "Failed to serialize {} event, {e}",
+                                envelope.event.event_type()
+                            )
+                        }
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("Event stream subscriber lagged, skipped {skipped} events");
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    warp::sse::reply(warp::sse::keep_alive().stream(stream)).into_response()
+}
+
+#[derive(Deserialize)]
+struct PairingBody {
+    code: String,
+    name: Option<String>,
+}
+
+async fn pair(
+    body: PairingBody,
+    auth: std::sync::Arc<tokio::sync::Mutex<RestApiAuth>>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let mut auth = auth.lock().await;
+    let name = body.name.unwrap_or("browser-extension".to_owned());
+    match auth.pair(&body.code, &name) {
+        Some(result) => Ok(warp::reply::json(&result).into_response()),
+        None => Err(warp::reject::custom(Unauthorized)),
+    }
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+impl warp::reject::Reject for Unauthorized {}
+
+#[derive(Debug)]
+struct Forbidden;
+impl warp::reject::Reject for Forbidden {}
+
+fn allowed_origin() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("origin")
+        .and_then(|origin: Option<String>| async move {
+            match origin {
+                None => Ok(()),
+                Some(origin) if rest_api_auth::is_allowed_origin(&origin) => Ok(()),
+                Some(origin) => {
+                    log::warn!("Rejecting rest api request from origin={origin}");
+                    Err(warp::reject::custom(Forbidden))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+fn authorized(
+    auth: std::sync::Arc<tokio::sync::Mutex<RestApiAuth>>,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(with_auth(auth))
+        .and_then(
+            |authorization: Option<String>,
+             auth: std::sync::Arc<tokio::sync::Mutex<RestApiAuth>>| async move {
+                let token = authorization
+                    .as_deref()
+                    .and_then(|value| value.strip_prefix("Bearer "));
+                match token {
+                    Some(token) if auth.lock().await.authorize(token) => Ok(()),
+                    _ => Err(warp::reject::custom(Unauthorized)),
+                }
+            },
+        )
+        .untuple_one()
+}
+
+async fn handle_rejection(
+    err: warp::Rejection,
+) -> Result<warp::reply::Response, std::convert::Infallible> {
+    let status = if err.find::<Unauthorized>().is_some() {
+        StatusCode::UNAUTHORIZED
+    } else if err.find::<Forbidden>().is_some() {
+        StatusCode::FORBIDDEN
+    } else if err.is_not_found() {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    Ok(warp::reply::with_status(warp::reply(), status).into_response())
+}
+
+fn with_auth(
+    auth: std::sync::Arc<tokio::sync::Mutex<RestApiAuth>>,
+) -> impl Filter<
+    Extract = (std::sync::Arc<tokio::sync::Mutex<RestApiAuth>>,),
+    Error = std::convert::Infallible,
+> + Clone {
+    warp::any().map(move || auth.clone())
+}
+
+fn with_events(
+    events: EventStream,
+) -> impl Filter<Extract = (EventStream,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || events.clone())
+}
+
 fn with_jar(
     jar: std::sync::Arc<tokio::sync::Mutex<CookieJar>>,
 ) -> impl Filter<
@@ -115,12 +254,84 @@ fn with_wombat_api(
     warp::any().map(move || wombat_api.clone())
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Discovery {
+    port: u16,
+    pid: u32,
+    version: String,
+    started_at: chrono::DateTime<Utc>,
+}
+
+fn discovery_file() -> std::path::PathBuf {
+    crate::user::wombat_dir().join("rest-api.json")
+}
+
+fn write_discovery_file(port: u16) {
+    let discovery = Discovery {
+        port,
+        pid: std::process::id(),
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        started_at: Utc::now(),
+    };
+    let path = discovery_file();
+    let json =
+        serde_json::to_string_pretty(&discovery).expect("Failed to serialize rest api discovery");
+    match std::fs::write(&path, json) {
+        Ok(()) => log::info!("Rest api discovery file written to {}", path.display()),
+        Err(e) => log::warn!("Failed to write rest api discovery file, {e}"),
+    }
+}
+
+/// Removes the discovery file unless it was since rewritten by another running instance.
+pub fn remove_discovery_file() {
+    let path = discovery_file();
+    let owned = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|discovery| discovery.get("pid").and_then(|pid| pid.as_u64()))
+        .is_some_and(|pid| pid == u64::from(std::process::id()));
+    if !owned {
+        return;
+    }
+    match std::fs::remove_file(&path) {
+        Ok(()) => log::info!("Rest api discovery file removed from {}", path.display()),
+        Err(e) => log::warn!("Failed to remove rest api discovery file, {e}"),
+    }
+}
+
+fn bind(port: u16) -> std::io::Result<tokio::net::TcpListener> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    tokio::net::TcpListener::from_std(listener)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn serve(
+    port: u16,
     jar: std::sync::Arc<tokio::sync::Mutex<CookieJar>>,
     browser_ext: std::sync::Arc<tokio::sync::Mutex<BrowserExtension>>,
     wombat_api: std::sync::Arc<tokio::sync::Mutex<WombatApi>>,
-) {
+    auth: std::sync::Arc<tokio::sync::Mutex<RestApiAuth>>,
+    app_handle: tauri::AppHandle,
+    event_stream: EventStream,
+) -> Result<u16, String> {
+    // The listener is bound here and handed to warp, so the reported status is the real one.
+    let listener = match bind(port) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Rest api port {port} is not available, {e}");
+            return Err(format!("Port {port} is not available: {e}"));
+        }
+    };
+    write_discovery_file(port);
     tokio::task::spawn(async move {
+        let pairing = warp::post()
+            .and(warp::path!("pairing"))
+            .and(warp::body::json())
+            .and(with_auth(auth.clone()))
+            .and_then(pair);
+
         let routes = warp::put()
             .and(warp::path!("cookies"))
             .and(warp::body::json())
@@ -139,8 +350,54 @@ pub fn serve(
             .or(warp::post()
                 .and(warp::path!("browser-extension-event"))
                 .and(warp::body::json())
-                .and_then(browser_extension_event));
+                .and_then(browser_extension_event))
+            .or(warp::get()
+                .and(warp::path!("events"))
+                .and(with_events(event_stream))
+                .map(events))
+            .or(control_api::routes(app_handle));
+
+        let api = allowed_origin()
+            .and(pairing.or(authorized(auth.clone()).and(routes)))
+            .recover(handle_rejection);
 
-        warp::serve(routes).run(([127, 0, 0, 1], 6891)).await;
+        warp::serve(api).incoming(listener).run().await;
+        remove_discovery_file();
     });
+    Ok(port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_cookie_refresh_replaces_cookie_pushed_by_extension() {
+        let pushed: NewCookieDto = serde_json::from_value(serde_json::json!({
+            "name": "session",
+            "value": "old",
+            "env": "DEV",
+            "storedAt": 0,
+            "domain": ".Example.com",
+            "path": "/",
+        }))
+        .unwrap();
+        let mut jar = CookieJar {
+            cookies: vec![Cookie::from(pushed)],
+        };
+        // What the cookie authenticator stores for `Set-Cookie: session=new; Domain=.example.com`.
+        jar.put(Cookie {
+            name: "session".to_owned(),
+            value: "new".to_owned(),
+            env: Env::DEV,
+            stored_at: Utc::now(),
+            domain: Some("example.com".to_owned()),
+            path: Some("/".to_owned()),
+            expires_at: None,
+            secure: false,
+            host_only: false,
+        });
+        let url = url::Url::parse("https://app.example.com/api").unwrap();
+        assert_eq!(jar.header_value_for(&Env::DEV, &url), "session=new");
+    }
 }
@@ -0,0 +1,265 @@
+use crate::shared::CommandError;
+use crate::user::wombat_dir;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+const PAIRING_CODE_TTL_SECONDS: i64 = 5 * 60;
+const PAIRING_CODE_MAX_ATTEMPTS: u8 = 5;
+const ALLOWED_ORIGIN_PREFIXES: [&str; 3] = [
+    "chrome-extension://",
+    "moz-extension://",
+    "safari-web-extension://",
+];
+
+/// Browser extensions are the only web origins allowed to call the rest api.
+pub fn is_allowed_origin(origin: &str) -> bool {
+    ALLOWED_ORIGIN_PREFIXES
+        .iter()
+        .any(|prefix| origin.starts_with(prefix))
+}
+
+struct PendingPairing {
+    code: String,
+    expires_at: DateTime<Utc>,
+    failed_attempts: u8,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct PairedClient {
+    id: Uuid,
+    name: String,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingCode {
+    pub code: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PairedClientInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingResult {
+    pub client_id: Uuid,
+    pub token: String,
+}
+
+pub struct RestApiAuth {
+    pending_pairing: Option<PendingPairing>,
+    clients: Vec<PairedClient>,
+}
+
+impl RestApiAuth {
+    pub fn load() -> RestApiAuth {
+        let clients = match std::fs::read_to_string(RestApiAuth::clients_path()) {
+            Ok(json) => serde_json::from_str::<Vec<PairedClient>>(&json).unwrap_or_else(|e| {
+                warn!("Failed to read paired rest api clients, reason: {e}");
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        RestApiAuth {
+            pending_pairing: None,
+            clients,
+        }
+    }
+
+    fn clients_path() -> PathBuf {
+        wombat_dir().join("rest-api-clients.json")
+    }
+
+    fn save(&self) -> Result<(), CommandError> {
+        let json = serde_json::to_string_pretty(&self.clients)
+            .map_err(|e| CommandError::new("rest_api_auth", e.to_string()))?;
+        std::fs::write(RestApiAuth::clients_path(), json)
+            .map_err(|e| CommandError::new("rest_api_auth", e.to_string()))
+    }
+
+    fn hash(token: &str) -> String {
+        format!("{:X}", Sha256::new().chain_update(token).finalize())
+    }
+
+    pub fn new_pairing_code(&mut self) -> PairingCode {
+        let code = format!("{:06}", rand::rng().random_range(0..1_000_000));
+        let expires_at = Utc::now() + chrono::Duration::seconds(PAIRING_CODE_TTL_SECONDS);
+        self.pending_pairing = Some(PendingPairing {
+            code: code.clone(),
+            expires_at,
+            failed_attempts: 0,
+        });
+        info!("Generated rest api pairing code, valid until {expires_at}");
+        PairingCode { code, expires_at }
+    }
+
+    pub fn pair(&mut self, code: &str, name: &str) -> Option<PairingResult> {
+        let pending = self.pending_pairing.as_mut()?;
+        if pending.expires_at < Utc::now() {
+            warn!("Rest api pairing code expired");
+            self.pending_pairing = None;
+            return None;
+        }
+        // Compared in constant time, the response time must not tell how many digits match.
+        if !bool::from(pending.code.as_bytes().ct_eq(code.as_bytes())) {
+            pending.failed_attempts += 1;
+            warn!(
+                "Invalid rest api pairing code, attempt {}",
+                pending.failed_attempts
+            );
+            if pending.failed_attempts >= PAIRING_CODE_MAX_ATTEMPTS {
+                self.pending_pairing = None;
+            }
+            return None;
+        }
+        self.pending_pairing = None;
+
+        let result = self.add_client(name);
+        if let Err(e) = self.save() {
+            warn!("Failed to persist paired rest api client, {}", e.message);
+        }
+        Some(result)
+    }
+
+    fn add_client(&mut self, name: &str) -> PairingResult {
+        let token: String = rand::rng()
+            .random::<[u8; 32]>()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let client = PairedClient {
+            id: Uuid::new_v4(),
+            name: name.to_owned(),
+            token_hash: RestApiAuth::hash(&token),
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        info!("Paired rest api client {} ({})", &client.name, client.id);
+        let client_id = client.id;
+        self.clients.push(client);
+        PairingResult { client_id, token }
+    }
+
+    pub fn authorize(&mut self, token: &str) -> bool {
+        let token_hash = RestApiAuth::hash(token);
+        match self
+            .clients
+            .iter_mut()
+            .find(|client| bool::from(client.token_hash.as_bytes().ct_eq(token_hash.as_bytes())))
+        {
+            Some(client) => {
+                client.last_used_at = Some(Utc::now());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clients(&self) -> Vec<PairedClientInfo> {
+        self.clients
+            .iter()
+            .map(|client| PairedClientInfo {
+                id: client.id,
+                name: client.name.clone(),
+                created_at: client.created_at,
+                last_used_at: client.last_used_at,
+            })
+            .collect()
+    }
+
+    pub fn revoke(&mut self, client_id: Uuid) -> Result<(), CommandError> {
+        let count = self.clients.len();
+        self.clients.retain(|client| client.id != client_id);
+        if count == self.clients.len() {
+            return Err(CommandError::new(
+                "revoke_rest_api_client",
+                format!("Unknown client {client_id}"),
+            ));
+        }
+        info!("Revoked rest api client {client_id}");
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth_with_code(code: &str, expires_at: DateTime<Utc>) -> RestApiAuth {
+        RestApiAuth {
+            pending_pairing: Some(PendingPairing {
+                code: code.to_owned(),
+                expires_at,
+                failed_attempts: 0,
+            }),
+            clients: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rejects_expired_pairing_codes() {
+        let mut auth = auth_with_code("123456", Utc::now() - chrono::Duration::seconds(1));
+        assert!(auth.pair("123456", "extension").is_none());
+        assert!(auth.pending_pairing.is_none());
+    }
+
+    #[test]
+    fn drops_pairing_code_after_too_many_attempts() {
+        let mut auth = auth_with_code("123456", Utc::now() + chrono::Duration::minutes(1));
+        for attempt in 1..PAIRING_CODE_MAX_ATTEMPTS {
+            assert!(auth.pair("000000", "extension").is_none());
+            assert_eq!(
+                auth.pending_pairing
+                    .as_ref()
+                    .map(|pending| pending.failed_attempts),
+                Some(attempt)
+            );
+        }
+        assert!(auth.pair("12345", "extension").is_none());
+        assert!(auth.pending_pairing.is_none());
+        assert!(auth.pair("123456", "extension").is_none());
+    }
+
+    #[test]
+    fn stores_only_the_hash_of_issued_tokens() {
+        let mut auth = RestApiAuth {
+            pending_pairing: None,
+            clients: Vec::new(),
+        };
+        let result = auth.add_client("extension");
+        assert_ne!(auth.clients[0].token_hash, result.token);
+        assert_eq!(auth.clients[0].token_hash, RestApiAuth::hash(&result.token));
+        assert!(auth.clients[0].last_used_at.is_none());
+
+        assert!(auth.authorize(&result.token));
+        assert!(auth.clients[0].last_used_at.is_some());
+        assert!(!auth.authorize(&result.token.to_uppercase()));
+        assert!(!auth.authorize(""));
+    }
+
+    #[test]
+    fn allows_only_extension_origins() {
+        assert!(is_allowed_origin("chrome-extension://abcdef"));
+        assert!(is_allowed_origin("moz-extension://abcdef"));
+        assert!(is_allowed_origin("safari-web-extension://abcdef"));
+        assert!(!is_allowed_origin("https://example.com"));
+        assert!(!is_allowed_origin("https://chrome-extension.example.com"));
+        assert!(!is_allowed_origin("null"));
+    }
+}
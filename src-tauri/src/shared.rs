@@ -54,6 +54,81 @@ pub struct Cookie {
     pub value: String,
     pub env: Env,
     pub stored_at: DateTime<Utc>,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub secure: bool,
+    /// Set without a Domain attribute, only sent back to the host that set it.
+    #[serde(default)]
+    pub host_only: bool,
+}
+
+/// Cookie domains are kept without a leading dot and in lower case, RFC 6265 section 5.2.3.
+pub fn normalize_cookie_domain(domain: &str) -> String {
+    domain.trim_start_matches('.').to_lowercase()
+}
+
+/// Domain matching of RFC 6265 section 5.1.3, `host` is the request host.
+pub fn domain_matches(host: &str, domain: &str) -> bool {
+    let domain = normalize_cookie_domain(domain);
+    let host = host.to_lowercase();
+    if host == domain {
+        return true;
+    }
+    // IP addresses only match themselves.
+    host.parse::<std::net::IpAddr>().is_err() && host.ends_with(&format!(".{domain}"))
+}
+
+impl Cookie {
+    /// A stored cookie is replaced by one with the same name, env, path and domain, a cookie
+    /// without a domain stands for every domain.
+    pub fn same_identity(&self, other: &Cookie) -> bool {
+        let path = |cookie: &Cookie| {
+            cookie
+                .path
+                .clone()
+                .filter(|path| !path.is_empty())
+                .unwrap_or(String::from("/"))
+        };
+        let domain = |cookie: &Cookie| cookie.domain.as_deref().map(normalize_cookie_domain);
+        self.name == other.name
+            && self.env == other.env
+            && path(self) == path(other)
+            && match (domain(self), domain(other)) {
+                (Some(domain), Some(other_domain)) => domain == other_domain,
+                _ => true,
+            }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    fn matches_domain(&self, host: &str) -> bool {
+        match &self.domain {
+            None => true,
+            Some(domain) if self.host_only => host.eq_ignore_ascii_case(domain),
+            Some(domain) => domain_matches(host, domain),
+        }
+    }
+
+    fn matches_path(&self, request_path: &str) -> bool {
+        match self.path.as_deref() {
+            None | Some("") | Some("/") => true,
+            Some(path) => {
+                request_path == path
+                    || (request_path.starts_with(path)
+                        && (path.ends_with('/') || request_path[path.len()..].starts_with('/')))
+            }
+        }
+    }
+
+    fn matches(&self, env: &Env, url: &url::Url) -> bool {
+        self.env == *env
+            && self.matches_domain(url.host_str().unwrap_or_default())
+            && self.matches_path(url.path())
+            && (!self.secure || url.scheme() == "https")
+    }
 }
 
 pub struct CookieJar {
@@ -61,15 +136,44 @@ pub struct CookieJar {
 }
 
 impl CookieJar {
-    pub fn header_value_for_env(&self, env: &Env) -> String {
+    pub fn put(&mut self, cookie: Cookie) {
         self.cookies
+            .retain(|existing| !existing.same_identity(&cookie));
+        self.cookies.push(cookie);
+    }
+
+    pub fn evict_expired(&mut self) {
+        let now = Utc::now();
+        self.cookies.retain(|cookie| {
+            let expired = cookie.is_expired(now);
+            if expired {
+                log::info!(
+                    "Evicting expired cookie {} for env={}",
+                    cookie.name,
+                    cookie.env
+                );
+            }
+            !expired
+        });
+    }
+
+    pub fn header_value_for(&mut self, env: &Env, url: &url::Url) -> String {
+        self.evict_expired();
+        let mut matching: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|c| c.matches(env, url))
+            .collect();
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.as_deref().unwrap_or("/").len()));
+        matching
             .iter()
-            .filter(|c| c.env == *env)
             .map(|c| format!("{}={}", c.name, c.value))
             .collect::<Vec<String>>()
             .join("; ")
     }
-    pub fn to_status(&self) -> CookieJarStatus {
+
+    pub fn to_status(&mut self) -> CookieJarStatus {
+        self.evict_expired();
         CookieJarStatus {
             cookie_health: self
                 .cookies
@@ -88,7 +192,7 @@ impl CookieJar {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CookieJarStatus {
     pub cookie_health: HashMap<Env, CookieHealth>,
@@ -108,7 +212,7 @@ pub struct BrowserExtensionStatus {
     pub version: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum CookieHealth {
     Ok,
     Stale,
@@ -205,3 +309,82 @@ pub fn arn_to_name(arn: &str) -> TrackedName {
     }
     format!("unknown!#{arn}")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie(name: &str, domain: Option<&str>, path: Option<&str>) -> Cookie {
+        Cookie {
+            name: name.to_owned(),
+            value: "v".to_owned(),
+            env: Env::DEV,
+            stored_at: Utc::now(),
+            domain: domain.map(|domain| domain.to_owned()),
+            path: path.map(|path| path.to_owned()),
+            expires_at: None,
+            secure: false,
+            host_only: false,
+        }
+    }
+
+    fn header(jar: &mut CookieJar, url: &str) -> String {
+        jar.header_value_for(&Env::DEV, &url::Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn domain_matches_host_and_its_parents() {
+        assert!(domain_matches("api.example.com", "example.com"));
+        assert!(domain_matches("API.example.com", ".Example.com"));
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(!domain_matches("badexample.com", "example.com"));
+        assert!(!domain_matches("example.com", "api.example.com"));
+        assert!(!domain_matches("10.0.0.1", "0.0.1"));
+    }
+
+    #[test]
+    fn sends_cookies_matching_domain_and_path() {
+        let mut jar = CookieJar {
+            cookies: vec![
+                cookie("any", None, None),
+                cookie("domain", Some("example.com"), None),
+                cookie("api", Some("example.com"), Some("/api")),
+                cookie("other", Some("other.com"), None),
+            ],
+        };
+        assert_eq!(
+            header(&mut jar, "https://app.example.com/api/users"),
+            "api=v; any=v; domain=v"
+        );
+        assert_eq!(
+            header(&mut jar, "https://app.example.com/apiv2"),
+            "any=v; domain=v"
+        );
+        assert_eq!(header(&mut jar, "https://other.com/"), "any=v; other=v");
+    }
+
+    #[test]
+    fn host_only_cookies_skip_subdomains() {
+        let mut host_only = cookie("session", Some("example.com"), None);
+        host_only.host_only = true;
+        let mut jar = CookieJar {
+            cookies: vec![host_only],
+        };
+        assert_eq!(header(&mut jar, "https://example.com/"), "session=v");
+        assert_eq!(header(&mut jar, "https://api.example.com/"), "");
+    }
+
+    #[test]
+    fn skips_secure_and_expired_cookies() {
+        let mut secure = cookie("secure", None, None);
+        secure.secure = true;
+        let mut expired = cookie("expired", None, None);
+        expired.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        let mut jar = CookieJar {
+            cookies: vec![secure, expired],
+        };
+        assert_eq!(header(&mut jar, "http://example.com/"), "");
+        assert_eq!(header(&mut jar, "https://example.com/"), "secure=v");
+        assert_eq!(jar.cookies.len(), 1);
+    }
+}
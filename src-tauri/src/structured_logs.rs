@@ -0,0 +1,211 @@
+use crate::aws::{LogEntry, LogSearchMonitor, SearchWarning};
+use crate::log_checkpoint::SearchProgress;
+use crate::shared::CommandError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::{Arc, LazyLock};
+use tokio::sync::Mutex;
+
+static PLAIN_LEVEL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(TRACE|DEBUG|INFO|WARN|ERROR)\b").unwrap());
+static CONDITION_SEPARATOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\s+and\s+").unwrap());
+
+const LOGGER_KEYS: [&str; 3] = ["logger", "logger_name", "loggerName"];
+const TRACE_ID_KEYS: [&str; 3] = ["traceId", "trace_id", "X-B3-TraceId"];
+const EXCEPTION_KEYS: [&str; 3] = ["exception", "stack_trace", "stackTrace"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct LogFields {
+    pub level: Option<String>,
+    pub logger: Option<String>,
+    pub trace_id: Option<String>,
+    pub mdc: Option<Map<String, Value>>,
+    pub exception: Option<String>,
+}
+
+/// Trace ids end up in filter patterns and dump file names, only plain id characters pass.
+pub fn is_valid_trace_id(trace_id: &str) -> bool {
+    !trace_id.is_empty()
+        && !trace_id.contains("..")
+        && trace_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | ':' | '-'))
+}
+
+fn string_field(object: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| object.get(*key))
+        .and_then(|value| match value {
+            Value::Null => None,
+            Value::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        })
+}
+
+pub fn parse(message: &str) -> LogFields {
+    let object = match serde_json::from_str::<Value>(message) {
+        Ok(Value::Object(object)) => object,
+        _ => {
+            return LogFields {
+                level: PLAIN_LEVEL
+                    .find(message)
+                    .map(|level| level.as_str().to_owned()),
+                ..LogFields::default()
+            }
+        }
+    };
+    let mdc = object.get("mdc").and_then(|mdc| mdc.as_object()).cloned();
+    LogFields {
+        level: string_field(&object, &["level", "severity"]).map(|level| level.to_uppercase()),
+        logger: string_field(&object, &LOGGER_KEYS),
+        trace_id: string_field(&object, &TRACE_ID_KEYS).or_else(|| {
+            mdc.as_ref()
+                .and_then(|mdc| string_field(mdc, &TRACE_ID_KEYS))
+        }),
+        exception: string_field(&object, &EXCEPTION_KEYS),
+        mdc,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FieldCondition {
+    field: String,
+    value: String,
+    negated: bool,
+}
+
+impl FieldCondition {
+    fn field_value(&self, entry: &LogEntry) -> Option<String> {
+        let fields = &entry.fields;
+        match self.field.as_str() {
+            "level" => fields.level.clone(),
+            "logger" => fields.logger.clone(),
+            "traceId" | "trace_id" => fields.trace_id.clone(),
+            "exception" => fields.exception.clone(),
+            "message" => Some(entry.message.clone()),
+            "stream" => Some(entry.log_stream_name.clone()),
+            field => field.strip_prefix("mdc.").and_then(|key| {
+                fields
+                    .mdc
+                    .as_ref()
+                    .and_then(|mdc| string_field(mdc, &[key]))
+            }),
+        }
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        let matches = match self.field_value(entry) {
+            Some(value) if self.field == "level" => value.eq_ignore_ascii_case(&self.value),
+            Some(value) if self.field == "message" || self.field == "exception" => {
+                value.contains(&self.value)
+            }
+            Some(value) => value == self.value,
+            None => false,
+        };
+        matches != self.negated
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldFilter {
+    conditions: Vec<FieldCondition>,
+}
+
+impl FieldFilter {
+    /// Parses `field=value` conditions joined with `and`, `!=` negates a condition.
+    pub fn parse(filter: &str) -> Result<FieldFilter, CommandError> {
+        let conditions = CONDITION_SEPARATOR
+            .split(filter.trim())
+            .filter(|condition| !condition.trim().is_empty())
+            .map(|condition| {
+                let (field, value, negated) = match condition.split_once("!=") {
+                    Some((field, value)) => (field, value, true),
+                    None => match condition.split_once('=') {
+                        Some((field, value)) => (field, value, false),
+                        None => {
+                            return Err(CommandError::new(
+                                "field_filter",
+                                format!("Invalid condition '{condition}', expected field=value"),
+                            ))
+                        }
+                    },
+                };
+                Ok(FieldCondition {
+                    field: field.trim().to_owned(),
+                    value: value.trim().trim_matches('"').to_owned(),
+                    negated,
+                })
+            })
+            .collect::<Result<Vec<FieldCondition>, CommandError>>()?;
+        Ok(FieldFilter { conditions })
+    }
+
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(entry))
+    }
+}
+
+pub struct FieldFilteringMonitor {
+    filter: Option<FieldFilter>,
+    inner: Box<dyn LogSearchMonitor>,
+}
+
+impl LogSearchMonitor for FieldFilteringMonitor {
+    fn notify(&mut self, logs: Vec<LogEntry>) {
+        let logs: Vec<LogEntry> = match &self.filter {
+            Some(filter) => logs.into_iter().filter(|log| filter.matches(log)).collect(),
+            None => logs,
+        };
+        if !logs.is_empty() {
+            self.inner.notify(logs);
+        }
+    }
+    fn success(&mut self, msg: String) {
+        self.inner.success(msg);
+    }
+    fn error(&mut self, msg: String) {
+        self.inner.error(msg);
+    }
+    fn message(&mut self, msg: String) {
+        self.inner.message(msg);
+    }
+    fn checkpoint(&mut self, progress: &SearchProgress) {
+        self.inner.checkpoint(progress);
+    }
+    fn warning(&mut self, warning: SearchWarning) {
+        self.inner.warning(warning);
+    }
+}
+
+pub fn filtered_monitor(
+    monitor: Box<dyn LogSearchMonitor>,
+    filter: Option<FieldFilter>,
+) -> Arc<Mutex<dyn LogSearchMonitor>> {
+    Arc::new(Mutex::new(FieldFilteringMonitor {
+        filter,
+        inner: monitor,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_trace_ids() {
+        assert!(is_valid_trace_id("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert!(is_valid_trace_id("1-5759e988-bd862e3fe1be46a994272793"));
+        assert!(is_valid_trace_id("req_42:span.1"));
+    }
+
+    #[test]
+    fn rejects_trace_ids_unsafe_in_paths_or_patterns() {
+        for trace_id in ["", "../etc", "a/b", "a\\b", "..", "a\"b", "a b"] {
+            assert!(!is_valid_trace_id(trace_id), "{trace_id}");
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::log_sources::LogNaming;
+use crate::log_watch::{LogWatch, LogWatchInput, LogWatchResult};
 use crate::shared::{CommandError, Env, TrackedName};
 use log::{error, info, warn};
 use rand::RngExt;
@@ -14,6 +16,7 @@ const ECS_PORT_RANGE: Range<u16> = 53000..53100;
 const LAMBDA_PORT_RANGE: Range<u16> = 54000..54100;
 const COOKIE_SESSION_PORT_RANGE: Range<u16> = 55000..55100;
 const FALLBACK_PORT_RANGE: Range<u16> = 58000..59000;
+pub const DEFAULT_REST_API_PORT: u16 = 6891;
 
 pub fn wombat_dir() -> PathBuf {
     home::home_dir().unwrap_or_log().as_path().join(".wombat")
@@ -23,6 +26,8 @@ pub fn wombat_dir() -> PathBuf {
 pub struct WombatAwsProfilePreferences {
     pub tracked_names: HashSet<TrackedName>,
     pub preferred_environments: Vec<Env>,
+    #[serde(default)]
+    pub log_naming: Option<LogNaming>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -34,6 +39,14 @@ pub struct UserConfig {
     pub dbeaver_path: Option<String>,
     pub logs_dir: PathBuf,
     pub preferences: HashMap<String, WombatAwsProfilePreferences>,
+    #[serde(default = "default_rest_api_port")]
+    pub rest_api_port: u16,
+    #[serde(default)]
+    pub log_watches: Vec<LogWatch>,
+}
+
+fn default_rest_api_port() -> u16 {
+    DEFAULT_REST_API_PORT
 }
 
 impl UserConfig {
@@ -78,6 +91,8 @@ impl UserConfig {
                 dbeaver_path: None,
                 logs_dir: UserConfig::logs_path(),
                 preferences: HashMap::new(),
+                rest_api_port: DEFAULT_REST_API_PORT,
+                log_watches: Vec::new(),
             },
         };
 
@@ -134,6 +149,103 @@ impl UserConfig {
         Ok(self.clone())
     }
 
+    pub fn set_log_naming(
+        &mut self,
+        profile_name: &str,
+        log_naming: Option<LogNaming>,
+    ) -> Result<UserConfig, CommandError> {
+        if let Some(invalid) = log_naming.as_ref().and_then(|naming| {
+            naming
+                .rules
+                .iter()
+                .find(|rule| rule.group_prefix.is_empty() || !rule.stream_prefix.contains("{app}"))
+        }) {
+            return Err(CommandError::new(
+                "set_log_naming",
+                format!(
+                    "Rule {} needs a group prefix and a stream prefix containing {{app}}",
+                    invalid.app_type
+                ),
+            ));
+        }
+        let preference = self.preferences.get_mut(profile_name).ok_or_else(|| {
+            CommandError::new("set_log_naming", format!("Unknown profile {profile_name}"))
+        })?;
+        preference.log_naming = log_naming;
+        self.save();
+        Ok(self.clone())
+    }
+
+    pub fn log_naming(&self, profile_name: &str) -> Option<LogNaming> {
+        self.preferences
+            .get(profile_name)
+            .and_then(|preference| preference.log_naming.clone())
+    }
+
+    pub fn save_log_watch(&mut self, input: LogWatchInput) -> Result<UserConfig, CommandError> {
+        if input.apps.is_empty() {
+            return Err(CommandError::new("save_log_watch", "Log watch needs apps"));
+        }
+        if input.interval_minutes < 1 {
+            return Err(CommandError::new(
+                "save_log_watch",
+                "Log watch interval must be at least 1 minute",
+            ));
+        }
+        if input.threshold < 1 {
+            return Err(CommandError::new(
+                "save_log_watch",
+                "Log watch threshold must be at least 1 log",
+            ));
+        }
+        let id = input.id.unwrap_or_else(Uuid::new_v4);
+        let history = self
+            .log_watches
+            .iter()
+            .find(|watch| watch.id == id)
+            .map(|watch| watch.history.clone())
+            .unwrap_or_default();
+        let watch = LogWatch {
+            id,
+            name: input.name,
+            apps: input.apps,
+            env: input.env,
+            filter: input.filter,
+            interval_minutes: input.interval_minutes,
+            threshold: input.threshold,
+            enabled: input.enabled,
+            history,
+            // Saving a watch gives it a fresh start after failures disabled it.
+            consecutive_failures: 0,
+        };
+        match self
+            .log_watches
+            .iter_mut()
+            .find(|existing| existing.id == id)
+        {
+            Some(existing) => *existing = watch,
+            None => self.log_watches.push(watch),
+        }
+        self.save();
+        Ok(self.clone())
+    }
+
+    pub fn delete_log_watch(&mut self, id: &Uuid) -> Result<UserConfig, CommandError> {
+        self.log_watches.retain(|watch| &watch.id != id);
+        self.save();
+        Ok(self.clone())
+    }
+
+    /// Returns true when the result disabled the watch.
+    pub fn record_log_watch_result(&mut self, id: &Uuid, result: LogWatchResult) -> bool {
+        let Some(watch) = self.log_watches.iter_mut().find(|watch| &watch.id == id) else {
+            return false;
+        };
+        let disabled = watch.record(result);
+        self.save();
+        disabled
+    }
+
     fn get_port(map: &mut HashMap<String, u16>, arn: &str, range: Range<u16>) -> (u16, bool) {
         if let Some(port) = map.get(arn) {
             return (*port, false);
@@ -163,6 +275,12 @@ impl UserConfig {
         (assigned_port, true)
     }
 
+    pub fn assigned_port(&self, arn: &str) -> Option<u16> {
+        self.arn_to_proxy_port_map
+            .as_ref()
+            .and_then(|map| map.get(arn).copied())
+    }
+
     pub fn get_db_port(&mut self, db_arn: &str) -> u16 {
         let port = Self::get_port(
             self.arn_to_proxy_port_map.as_mut().unwrap_or_log(),
@@ -220,6 +338,18 @@ impl UserConfig {
             Err(CommandError::new("set_dbeaver_path", "Invalid path!"))
         }
     }
+    pub fn set_rest_api_port(&mut self, port: u16) -> Result<UserConfig, CommandError> {
+        if port < 1024 {
+            return Err(CommandError::new(
+                "set_rest_api_port",
+                format!("Port {port} is reserved, use 1024 or above"),
+            ));
+        }
+        self.rest_api_port = port;
+        self.save();
+        Ok(self.clone())
+    }
+
     pub fn set_logs_path(&mut self, logs_dir_path: &str) -> Result<UserConfig, CommandError> {
         let path = std::path::Path::new(logs_dir_path);
         let res = fs::create_dir_all(path);
@@ -246,6 +376,7 @@ impl UserConfig {
                 WombatAwsProfilePreferences {
                     tracked_names,
                     preferred_environments: envs,
+                    log_naming: None,
                 },
             );
         }
@@ -1,8 +1,11 @@
+use crate::log_sources::LogNaming;
+use crate::shared::CommandError;
 use base64::Engine;
 use chrono::{DateTime, Utc};
 use futures::TryFutureExt;
 use log::info;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 pub struct WombatApi {
     user_id: uuid::Uuid,
@@ -160,24 +163,84 @@ impl WombatApi {
         vec![]
     }
 
-    pub async fn get_proxy_auth_configs(&mut self) -> Vec<ProxyAuthConfig> {
+    pub async fn propose_log_filter(&mut self, log_filter: &LogFilter) -> Result<(), String> {
+        log::info!("proposing log filter {}", &log_filter.label);
+        self.auth().await;
+        let client = self
+            .client()
+            .ok_or_else(|| "Wombat backend is not available".to_owned())?;
+        client
+            .post(format!("{}/api/log-filters/proposals", self.url))
+            .json(&LogFilterProposal {
+                filter: &log_filter.filter,
+                services: &log_filter.services,
+                label: &log_filter.label,
+            })
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| format!("Proposing log filter failed, {e}"))
+    }
+
+    pub async fn log_naming(&mut self) -> Option<LogNaming> {
+        log::info!("getting log naming rules");
+        self.auth().await;
+        let client = self.client()?;
+        let body = client
+            .get(format!("{}/api/log-naming", self.url))
+            .send()
+            .and_then(|response| response.json::<LogNaming>())
+            .await;
+        match body {
+            Ok(body) => Some(body),
+            Err(e) => {
+                log::warn!("fetching log naming rules failed, error: {e}");
+                None
+            }
+        }
+    }
+
+    pub async fn get_proxy_auth_configs(&mut self) -> Result<ProxyAuthConfigs, CommandError> {
         log::info!("getting proxy auth configs");
         self.auth().await;
-        if let Some(client) = self.client() {
-            let body = client
-                .get(format!("{}/api/proxy-auth-configs", self.url))
-                .send()
-                .and_then(|response| response.json::<Vec<ProxyAuthConfig>>())
-                .await;
-            return match body {
-                Ok(body) => body,
-                Err(e) => {
-                    log::error!("fetching proxy auth configs failed, error: {e}");
-                    vec![]
+        let client = self.client().ok_or(CommandError::new(
+            "proxy_auth_configs",
+            "Not authenticated with wombat backend",
+        ))?;
+        let body = client
+            .get(format!("{}/api/proxy-auth-configs", self.url))
+            .send()
+            .and_then(|response| response.json::<Vec<serde_json::Value>>())
+            .await
+            .map_err(|e| {
+                log::error!("fetching proxy auth configs failed, error: {e}");
+                CommandError::new(
+                    "proxy_auth_configs",
+                    format!("Fetching proxy auth configs failed, {e}"),
+                )
+            })?;
+
+        let mut configs = ProxyAuthConfigs {
+            configs: Vec::new(),
+            invalid: Vec::new(),
+        };
+        for record in body {
+            let id = record.get("id").cloned().unwrap_or_default();
+            let config = serde_json::from_value::<ProxyAuthConfig>(record)
+                .map_err(|e| format!("Invalid proxy auth config id={id}: {e}"))
+                .and_then(|config| config.validate().map(|_| config));
+            match config {
+                Ok(config) => configs.configs.push(config),
+                Err(msg) => {
+                    log::warn!("{msg}");
+                    configs
+                        .invalid
+                        .push(CommandError::new("proxy_auth_configs", msg));
                 }
-            };
+            }
         }
-        vec![]
+        Ok(configs)
     }
 
     pub async fn report_versions(&mut self, browser_extension: Option<String>) -> bool {
@@ -213,12 +276,29 @@ struct Versions {
     app: String,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFilterSource {
+    #[default]
+    Remote,
+    Local,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogFilter {
-    id: i64,
-    filter: String,
-    services: Vec<String>,
-    label: String,
+    pub id: i64,
+    pub filter: String,
+    pub services: Vec<String>,
+    pub label: String,
+    #[serde(default)]
+    pub source: LogFilterSource,
+}
+
+#[derive(Serialize, Debug)]
+struct LogFilterProposal<'a> {
+    filter: &'a str,
+    services: &'a [String],
+    label: &'a str,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -229,18 +309,135 @@ pub struct ProxyAuthConfig {
     pub to_app: String,
     pub env: String,
 
-    pub auth_type: String,
     pub api_path: String,
+    pub require_sso_profile: bool,
 
-    pub jepsen_auth_api: Option<String>,
-    pub jepsen_api_name: Option<String>,
-    pub jepsen_client_id: Option<String>,
+    #[serde(flatten)]
+    pub auth: ProxyAuth,
+}
 
-    pub basic_user: Option<String>,
+impl ProxyAuthConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let required = |name: &str, value: &str| {
+            if value.trim().is_empty() {
+                Err(format!(
+                    "Invalid {} proxy auth config id={}: missing {name}",
+                    self.auth.auth_type(),
+                    self.id
+                ))
+            } else {
+                Ok(())
+            }
+        };
+        match &self.auth {
+            ProxyAuth::Jepsen {
+                jepsen_auth_api,
+                jepsen_api_name,
+                jepsen_client_id,
+                secret_name,
+            } => {
+                required("jepsenAuthApi", jepsen_auth_api)?;
+                required("jepsenApiName", jepsen_api_name)?;
+                required("jepsenClientId", jepsen_client_id)?;
+                required("secretName", secret_name)
+            }
+            ProxyAuth::Basic {
+                basic_user,
+                secret_name,
+            } => {
+                required("basicUser", basic_user)?;
+                required("secretName", secret_name)
+            }
+            ProxyAuth::Jwt {
+                jwt_algorithm,
+                jwt_claims,
+                jwt_ttl_seconds,
+                secret_name,
+                ..
+            } => {
+                required("secretName", secret_name)?;
+                if let Some(algorithm) = jwt_algorithm {
+                    jsonwebtoken::Algorithm::from_str(algorithm).map_err(|_| {
+                        format!(
+                            "Invalid jwt proxy auth config id={}: unsupported algorithm {algorithm}",
+                            self.id
+                        )
+                    })?;
+                }
+                if jwt_claims
+                    .as_ref()
+                    .is_some_and(|claims| !claims.is_object())
+                {
+                    return Err(format!(
+                        "Invalid jwt proxy auth config id={}: claims must be an object",
+                        self.id
+                    ));
+                }
+                if jwt_ttl_seconds.is_some_and(|ttl| ttl <= 0) {
+                    return Err(format!(
+                        "Invalid jwt proxy auth config id={}: ttl must be positive",
+                        self.id
+                    ));
+                }
+                Ok(())
+            }
+            ProxyAuth::Mtls {
+                mtls_cert,
+                mtls_key,
+            } => {
+                required("mtlsCert", mtls_cert)?;
+                required("mtlsKey", mtls_key)
+            }
+        }
+    }
+}
 
-    pub secret_name: String,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "authType",
+    rename_all = "lowercase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ProxyAuth {
+    Jepsen {
+        jepsen_auth_api: String,
+        jepsen_api_name: String,
+        jepsen_client_id: String,
+        secret_name: String,
+    },
+    Basic {
+        basic_user: String,
+        secret_name: String,
+    },
+    Jwt {
+        jwt_algorithm: Option<String>,
+        jwt_claims: Option<serde_json::Value>,
+        jwt_ttl_seconds: Option<i64>,
+        jwt_per_request: Option<bool>,
+        secret_name: String,
+    },
+    Mtls {
+        mtls_cert: String,
+        mtls_key: String,
+    },
+}
 
-    pub require_sso_profile: bool,
+impl ProxyAuth {
+    pub fn auth_type(&self) -> &'static str {
+        match self {
+            ProxyAuth::Jepsen { .. } => "jepsen",
+            ProxyAuth::Basic { .. } => "basic",
+            ProxyAuth::Jwt { .. } => "jwt",
+            ProxyAuth::Mtls { .. } => "mtls",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyAuthConfigs {
+    pub configs: Vec<ProxyAuthConfig>,
+    pub invalid: Vec<CommandError>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
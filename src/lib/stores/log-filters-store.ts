@@ -1,24 +1,25 @@
 import { invoke } from '@tauri-apps/api/core';
 
-import type { LogFilter } from '$lib/types';
+import type { LogFilter, LogFilterInput } from '$lib/types';
+import { execute } from './error-store';
 import { derived, writable } from 'svelte/store';
 import { listen } from '@tauri-apps/api/event';
 
 const logFilters = writable<LogFilter[]>();
 const loading = writable(false);
+const load = () =>
+	invoke<LogFilter[]>('log_filters')
+		.then(logFilters.set)
+		.catch(() => {
+			logFilters.set([]);
+		})
+		.finally(() => {
+			loading.set(false);
+		});
 const refresh = () => {
 	loading.set(true);
 	console.log('refreshing');
-	setTimeout(() => {
-		invoke<LogFilter[]>('log_filters')
-			.then(logFilters.set)
-			.catch(() => {
-				logFilters.set([]);
-			})
-			.finally(() => {
-				loading.set(false);
-			});
-	}, 3000);
+	setTimeout(load, 3000);
 };
 refresh();
 
@@ -26,9 +27,31 @@ listen('cache-refreshed', () => {
 	refresh();
 });
 
-export const logFiltersStore = derived([logFilters, loading], ([filters, isLoading]) => {
+const saveLocal = async (filter: LogFilterInput, id?: number) => {
+	if (id === undefined) {
+		await execute<LogFilter>('create_log_filter', { filter }, true);
+	} else {
+		await execute<LogFilter>('update_log_filter', { id, filter }, true);
+	}
+	await load();
+};
+const deleteLocal = async (id: number) => {
+	await execute('delete_log_filter', { id }, true);
+	await load();
+};
+const propose = async (id: number) => {
+	await execute('propose_log_filter', { id }, true);
+};
+
+const state = derived([logFilters, loading], ([filters, isLoading]) => {
 	return {
 		filters,
 		isLoading
 	};
 });
+export const logFiltersStore = {
+	subscribe: state.subscribe,
+	saveLocal,
+	deleteLocal,
+	propose
+};
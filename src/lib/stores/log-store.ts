@@ -8,6 +8,71 @@ type LogEntry = {
 	timestamp: number;
 	ingestion_time: number;
 	message: string;
+	app: string | null;
+	env: AwsEnv | null;
+	level: string | null;
+	logger: string | null;
+	trace_id: string | null;
+	mdc: { [key: string]: unknown } | null;
+	exception: string | null;
+};
+export type LogSearchMode = 'filter' | 'insights';
+export type DumpFormat = 'ndjson' | 'csv' | 'text' | 'json';
+export type DumpSettings = {
+	format: DumpFormat;
+	gzip: boolean;
+	maxFileSizeMb: number | null;
+};
+export type SummaryMode = 'off' | 'alongside' | 'instead';
+export type LogTemplateSummary = {
+	template: string;
+	level: string | null;
+	count: number;
+	example: string;
+	firstSeen: number;
+	lastSeen: number;
+	apps: { [app: string]: number };
+	buckets: { [timestamp: string]: number };
+};
+export type LogSummary = {
+	total: number;
+	bucketMs: number;
+	templates: LogTemplateSummary[];
+	histogram: { timestamp: number; count: number; apps: { [app: string]: number } }[];
+	finished: boolean;
+};
+export type LimitPolicy = 'abort' | 'first' | 'last';
+export type SearchLimit = {
+	max: number;
+	policy: LimitPolicy;
+};
+type SearchProgress = {
+	lastTimestamp: number | null;
+	entriesAtLastTimestamp: number;
+	logCount: number;
+};
+export type SearchWarning = {
+	message: string;
+	logCount: number;
+	resumeFrom: SearchProgress | null;
+};
+export type SearchCheckpoint = {
+	id: string;
+	apps: string[];
+	env: AwsEnv;
+	filename: string;
+	progress: { logCount: number };
+	updatedAt: string;
+};
+export type BackgroundSearch = {
+	id: string;
+	label: string;
+	message: string | undefined;
+	status: 'running' | 'success' | 'error' | 'aborted';
+};
+type SearchEvent<T> = {
+	searchId: string;
+	data: T;
 };
 type LogStyle = {
 	bg: string;
@@ -25,6 +90,7 @@ type UiLogEntry = {
 	data: LogData;
 	style: LogStyle;
 	app: string;
+	env: AwsEnv | null;
 	tagBox: {
 		adUserId: string;
 		adUserName: string;
@@ -66,21 +132,21 @@ function transformLog(newLog: LogEntry): Omit<UiLogEntry, 'id'> {
 	} catch {
 		isString = true;
 	}
-	let app = '-';
-	const streamParts = newLog.log_stream_name.split('/');
-	if (streamParts.length > 0) {
-		app = streamParts[1];
-	}
+	const app = newLog.app ?? '-';
+	const env = newLog.env ?? null;
 	if (isString) {
-		const level = (newLog.message.match(/(INFO|WARN|ERROR|DEBUG|TRACE)/)?.[0] ??
+		const level = (newLog.level ??
+			newLog.message.match(/(INFO|WARN|ERROR|DEBUG|TRACE)/)?.[0] ??
 			'UNKNOWN') as LogLevel;
 		return {
 			app,
+			env,
 			timestamp: newLog.timestamp,
 			level,
 			message: newLog.message,
 			data: {
 				app,
+				env,
 				timestamp:
 					typeof newLog.timestamp == 'number' || typeof newLog.timestamp == 'string'
 						? format(new Date(newLog.timestamp), 'yyyy-MM-dd HH:mm:ss.SSS')
@@ -93,24 +159,26 @@ function transformLog(newLog: LogEntry): Omit<UiLogEntry, 'id'> {
 		};
 	} else {
 		const logData = JSON.parse(newLog.message);
-		const level = logData?.level?.match(/(INFO|WARN|ERROR|DEBUG|TRACE)/)?.[0] ?? 'UNKNOWN';
+		const level = (newLog.level?.match(/(INFO|WARN|ERROR|DEBUG|TRACE)/)?.[0] ??
+			'UNKNOWN') as LogLevel;
+		const mdc = newLog.mdc ?? {};
 		return {
 			app,
+			env,
 			timestamp: newLog.timestamp,
 			level,
-			message: logData.message ?? logData.exception?.split('\n')?.at(0),
+			message: logData.message ?? newLog.exception?.split('\n')?.at(0),
 			data: logData,
 			style: logStyle(level),
-			tagBox:
-				logData['mdc'] && logData['mdc']['traceId']
-					? {
-							adUserId: logData['mdc']['adUserId'],
-							adUserName: logData['mdc']['userName'],
-							adUserIdColor: colorFromString(logData['mdc']['userName']),
-							requestTraceId: logData['mdc']['traceId'],
-							requestTraceIdColor: colorFromString(logData['mdc']['traceId'])
-						}
-					: null
+			tagBox: newLog.trace_id
+				? {
+						adUserId: mdc['adUserId'] as string,
+						adUserName: mdc['userName'] as string,
+						adUserIdColor: colorFromString(mdc['userName'] as string),
+						requestTraceId: newLog.trace_id,
+						requestTraceIdColor: colorFromString(newLog.trace_id)
+					}
+				: null
 		};
 	}
 }
@@ -163,18 +231,39 @@ const createLogStore = () => {
 	});
 
 	const filterString = writable<string>('');
+	const fieldFilter = writable<string>('');
+	const compareEnvs = writable<AwsEnv[]>([]);
+	const logGroups = writable<string>('');
+	const dumpSettings = writable<DumpSettings>({
+		format: 'ndjson',
+		gzip: false,
+		maxFileSizeMb: null
+	});
+	const searchMode = writable<LogSearchMode>('filter');
+	const checkpoints = writable<SearchCheckpoint[]>([]);
+	const summaryMode = writable<SummaryMode>('off');
+	const logSummary = writable<LogSummary | undefined>(undefined);
+	// File dumps and resumed searches run next to the interactive search.
+	const backgroundSearches = writable<BackgroundSearch[]>([]);
+	let activeSearchId: string | undefined = undefined;
+	const searchLimit = writable<SearchLimit>({ max: 10000, policy: 'first' });
+	// Arguments of the last interactive search, a truncated one can be continued into a file.
+	let lastSearch: { apps: string[]; env: AwsEnv; args: Record<string, unknown> } | undefined =
+		undefined;
 
 	const storeState = writable<{
 		showLogDetails: boolean;
 		message: string | undefined;
 		isLookingForLogs: boolean;
 		searchStatus: 'success' | 'error' | 'aborted' | undefined;
+		warning: SearchWarning | undefined;
 		logs: UiLogEntry[];
 	}>({
 		showLogDetails: false,
 		message: undefined,
 		isLookingForLogs: false,
 		searchStatus: undefined,
+		warning: undefined,
 		logs: []
 	});
 
@@ -189,39 +278,106 @@ const createLogStore = () => {
 		});
 	}
 
-	listen<LogEntry[]>('new-log-found', (event) => processLogs(event.payload));
+	const updateBackground = (searchId: string, update: Partial<BackgroundSearch>) => {
+		backgroundSearches.update((searches) =>
+			searches.map((search) => (search.id === searchId ? { ...search, ...update } : search))
+		);
+	};
+
+	listen<SearchEvent<LogEntry[]>>('new-log-found', (event) => {
+		if (event.payload.searchId === activeSearchId) {
+			processLogs(event.payload.data);
+		}
+	});
+	listen<SearchEvent<LogSummary>>('log-summary', (event) => {
+		if (event.payload.searchId === activeSearchId) {
+			logSummary.set(event.payload.data);
+		}
+	});
 
-	listen<string>('find-logs-success', (event) => {
+	listen<SearchEvent<string>>('find-logs-success', (event) => {
+		const { searchId, data } = event.payload;
+		if (searchId !== activeSearchId) {
+			loadCheckpoints();
+			updateBackground(searchId, { status: 'success', message: data });
+			return;
+		}
 		storeState.update((state) => {
 			return {
 				...state,
 				isLookingForLogs: false,
 				searchStatus: 'success',
-				message: event.payload
+				message: data
 			};
 		});
 	});
-	listen<string>('find-logs-error', (event) => {
+	const loadCheckpoints = async () => {
+		checkpoints.set(await invoke<SearchCheckpoint[]>('log_search_checkpoints'));
+	};
+
+	listen<SearchEvent<string>>('find-logs-error', (event) => {
+		const { searchId, data } = event.payload;
+		loadCheckpoints();
+		if (searchId !== activeSearchId) {
+			updateBackground(searchId, { status: 'error', message: data });
+			return;
+		}
 		storeState.update((state) => {
 			return {
 				...state,
 				isLookingForLogs: false,
 				searchStatus: 'error',
-				message: event.payload
+				message: data
 			};
 		});
 	});
 
-	listen<string>('find-logs-message', (event) => {
-		console.log('message:', event.payload);
+	listen<SearchEvent<SearchWarning>>('find-logs-warning', (event) => {
+		const { searchId, data } = event.payload;
+		if (searchId !== activeSearchId) {
+			updateBackground(searchId, { message: data.message });
+			return;
+		}
+		storeState.update((state) => {
+			return {
+				...state,
+				warning: data
+			};
+		});
+	});
+
+	listen<SearchEvent<string>>('find-logs-message', (event) => {
+		const { searchId, data } = event.payload;
+		console.log('message:', searchId, data);
+		if (searchId !== activeSearchId) {
+			updateBackground(searchId, { message: data });
+			return;
+		}
 		storeState.update((state) => {
 			return {
 				...state,
-				message: event.payload
+				message: data
 			};
 		});
 	});
 
+	const startInteractive = () => {
+		if (activeSearchId) {
+			invoke('abort_find_logs', { reason: 'new-search', searchId: activeSearchId });
+		}
+		activeSearchId = crypto.randomUUID();
+		logSummary.set(undefined);
+		return activeSearchId;
+	};
+
+	const startBackground = (label: string, searchId: string = crypto.randomUUID()) => {
+		backgroundSearches.update((searches) => [
+			...searches.filter((search) => search.id !== searchId),
+			{ id: searchId, label, message: 'Starting...', status: 'running' }
+		]);
+		return searchId;
+	};
+
 	const timerangeToPartial = (
 		timerange: Timerange
 	): { startTimestamp: number; endTimestamp: number } => {
@@ -250,46 +406,181 @@ const createLogStore = () => {
 		}
 	};
 
+	const explicitLogGroups = () =>
+		get(logGroups)
+			.split(',')
+			.map((group) => group.trim())
+			.filter((group) => group.length > 0);
+
+	const findLogs = (
+		apps: string[],
+		env: AwsEnv,
+		searchId: string,
+		args: Record<string, unknown>
+	) => {
+		const mode = get(summaryMode);
+		args = {
+			...args,
+			searchId,
+			logGroups: explicitLogGroups(),
+			limit: get(searchLimit),
+			summary: mode === 'off' ? null : { mode }
+		};
+		const envs = [env, ...get(compareEnvs).filter((compareEnv) => compareEnv !== env)];
+		if (envs.length > 1) {
+			invoke('find_logs_across_envs', { apps, envs, ...args });
+		} else {
+			invoke('find_logs', { apps, env, ...args });
+		}
+	};
+
 	const search = (apps: string[], env: AwsEnv) => {
-		invoke('find_logs', {
+		const args = {
+			...timerangeToPartial(get(timerange)),
+			filter: get(filterString),
+			mode: get(searchMode),
+			fieldFilter: get(fieldFilter)
+		};
+		lastSearch = { apps, env, args };
+		findLogs(apps, env, startInteractive(), args);
+		storeState.update((state) => {
+			return {
+				...state,
+				isLookingForLogs: true,
+				message: 'Search in progress...',
+				logs: [],
+				showLogDetails: false,
+				searchStatus: undefined,
+				warning: undefined
+			};
+		});
+	};
+	const searchLocal = (apps: string[]) => {
+		invoke('search_local_logs', {
+			searchId: startInteractive(),
 			apps,
-			env,
 			...timerangeToPartial(get(timerange)),
-			filter: get(filterString)
+			query: get(filterString)
 		});
 		storeState.update((state) => {
 			return {
 				...state,
 				isLookingForLogs: true,
-				message: 'Search in progress...',
+				message: 'Searching local dumps...',
+				logs: [],
+				showLogDetails: false,
+				searchStatus: undefined,
+				warning: undefined
+			};
+		});
+	};
+	const tail = (apps: string[], env: AwsEnv) => {
+		invoke('tail_logs', {
+			searchId: startInteractive(),
+			apps,
+			env,
+			filter: get(filterString),
+			fieldFilter: get(fieldFilter),
+			logGroups: explicitLogGroups()
+		});
+		storeState.update((state) => {
+			return {
+				...state,
+				isLookingForLogs: true,
+				message: 'Tailing logs...',
 				logs: [],
 				showLogDetails: false,
-				searchStatus: undefined
+				searchStatus: undefined,
+				warning: undefined
 			};
 		});
 	};
+	const dumpOptions = () => {
+		const { format, gzip, maxFileSizeMb } = get(dumpSettings);
+		return {
+			format,
+			gzip,
+			maxFileSize: maxFileSizeMb ? maxFileSizeMb * 1024 * 1024 : null
+		};
+	};
+
 	const dumpLogs = (apps: string[], env: AwsEnv) => {
+		const filename = `${apps.join('_')}-${env?.toLowerCase()}`;
+		findLogs(apps, env, startBackground(filename), {
+			...timerangeToPartial(get(timerange)),
+			filter: get(filterString),
+			filename,
+			mode: get(searchMode),
+			fieldFilter: get(fieldFilter),
+			dump: dumpOptions()
+		});
+	};
+
+	const continueToFile = () => {
+		const resumeFrom = get(storeState).warning?.resumeFrom;
+		if (!lastSearch || !resumeFrom) {
+			return;
+		}
+		const { apps, env, args } = lastSearch;
+		const filename = `${apps.join('_')}-${env?.toLowerCase()}-continued`;
 		invoke('find_logs', {
 			apps,
 			env,
+			...args,
+			searchId: startBackground(filename),
+			logGroups: explicitLogGroups(),
+			filename,
+			dump: dumpOptions(),
+			resumeFrom
+		});
+	};
+
+	const traceRequest = (traceId: string, env: AwsEnv, toFile: boolean) => {
+		invoke('trace_request', {
+			searchId: toFile ? startBackground(`trace ${traceId}`) : startInteractive(),
+			traceId,
+			env,
 			...timerangeToPartial(get(timerange)),
-			filter: get(filterString),
-			filename: `${apps.join('_')}-${env?.toLowerCase()}`
+			filename: toFile ? `trace-${env?.toLowerCase()}` : null,
+			dump: dumpOptions(),
+			limit: get(searchLimit)
 		});
+		if (toFile) {
+			return;
+		}
 		storeState.update((state) => {
 			return {
 				...state,
 				isLookingForLogs: true,
-				searchError: undefined,
+				message: `Tracing ${traceId}...`,
 				logs: [],
 				showLogDetails: false,
-				searchStatus: undefined
+				searchStatus: undefined,
+				warning: undefined
 			};
 		});
 	};
 
+	const resume = (searchId: string) => {
+		const checkpoint = get(checkpoints).find((checkpoint) => checkpoint.id === searchId);
+		invoke('resume_find_logs', {
+			searchId: startBackground(checkpoint?.filename ?? 'resumed search', searchId)
+		});
+	};
+
+	const abortBackground = (searchId: string) => {
+		invoke('abort_find_logs', { reason: 'user-request', searchId }).then(() => loadCheckpoints());
+		updateBackground(searchId, { status: 'aborted', message: 'Aborted' });
+	};
+
+	const dismissBackground = (searchId: string) => {
+		backgroundSearches.update((searches) => searches.filter((search) => search.id !== searchId));
+	};
+
 	const abort = (reason: string) => {
-		invoke('abort_find_logs', { reason });
+		if (activeSearchId) {
+			invoke('abort_find_logs', { reason, searchId: activeSearchId });
+		}
 		storeState.update((state) => {
 			return {
 				...state,
@@ -314,10 +605,28 @@ const createLogStore = () => {
 		showLog,
 		abort,
 		search,
+		tail,
+		searchLocal,
 		dumpLogs,
+		continueToFile,
+		resume,
+		abortBackground,
+		dismissBackground,
+		backgroundSearches,
+		traceRequest,
+		loadCheckpoints,
+		checkpoints,
+		summaryMode,
+		logSummary,
+		searchLimit,
 		selectedLog,
 		timerange,
 		filterString,
+		fieldFilter,
+		compareEnvs,
+		logGroups,
+		dumpSettings,
+		searchMode,
 		storeState
 	};
 };
@@ -331,11 +640,16 @@ listen('logged-out', () => {
 		unit: 'minutes'
 	});
 	logStore.filterString.set('');
+	logStore.fieldFilter.set('');
+	logStore.compareEnvs.set([]);
+	logStore.logGroups.set('');
+	logStore.searchMode.set('filter');
 	logStore.storeState.set({
 		showLogDetails: false,
 		message: undefined,
 		isLookingForLogs: false,
 		searchStatus: undefined,
+		warning: undefined,
 		logs: []
 	});
 });
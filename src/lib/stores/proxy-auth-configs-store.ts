@@ -1,19 +1,27 @@
-import type { ProxyAuthConfig } from '$lib/types';
+import type { ProxyAuthConfig, ProxyAuthConfigs } from '$lib/types';
 import { listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
 import { writable } from 'svelte/store';
+import { error } from './error-store';
 
 const createProxyAuthConfigsStore = () => {
 	const configsStore = writable<ProxyAuthConfig[]>([]);
-	invoke<ProxyAuthConfig[]>('proxy_auth_configs').then((configs) => {
-		console.log('configs', configs);
-		configsStore.set(configs);
-	});
+	const refresh = () =>
+		invoke<ProxyAuthConfigs>('proxy_auth_configs')
+			.then(({ configs, invalid }) => {
+				console.log('configs', configs);
+				configsStore.set(configs);
+				if (invalid.length > 0) {
+					error.set(invalid.map((e) => e.message).join('\n'));
+				}
+			})
+			.catch((e) => {
+				console.error('proxy auth configs', e);
+				error.set(`${e.message ?? e}`);
+			});
+	refresh();
 	listen('cache-refreshed', () => {
-		invoke<ProxyAuthConfig[]>('proxy_auth_configs').then((configs) => {
-			console.log('configs', configs);
-			configsStore.set(configs);
-		});
+		refresh();
 	});
 	return { ...configsStore };
 };
@@ -1,6 +1,6 @@
 import { writable } from 'svelte/store';
 import { listen } from '@tauri-apps/api/event';
-import type { ProxyAuthConfig } from '$lib/types';
+import type { InfraProfile, ProxyAuthConfig, SsoProfile } from '$lib/types';
 import { invoke } from '@tauri-apps/api/core';
 
 type TaskKilled = {
@@ -17,7 +17,9 @@ export type Task = {
 	name: string;
 	status: TaskStatus;
 	port?: number;
-	proxyAuthConfig?: ProxyAuthConfig;
+	proxyAuthConfigs?: ProxyAuthConfig[];
+	infraProfile?: InfraProfile;
+	ssoProfile?: SsoProfile;
 };
 
 export type NewTaskParams = {
@@ -25,7 +27,13 @@ export type NewTaskParams = {
 	proxyAuthConfig?: ProxyAuthConfig;
 };
 
-type TaskDef = { name: string; arn: string; proxyAuthConfig?: ProxyAuthConfig };
+type TaskDef = {
+	name: string;
+	arn: string;
+	proxyAuthConfigs?: ProxyAuthConfig[];
+	infraProfile?: InfraProfile;
+	ssoProfile?: SsoProfile;
+};
 
 const createTaskStore = () => {
 	const tasks = writable<Task[]>([]);
@@ -37,10 +45,17 @@ const createTaskStore = () => {
 	};
 
 	const startTask = async (
-		{ name, arn, proxyAuthConfig }: TaskDef,
+		{ name, arn, proxyAuthConfigs, infraProfile, ssoProfile }: TaskDef,
 		startTask: () => Promise<NewTaskParams>
 	) => {
-		updateToStatus({ arn, name, status: TaskStatus.STARTING, proxyAuthConfig });
+		updateToStatus({
+			arn,
+			name,
+			status: TaskStatus.STARTING,
+			proxyAuthConfigs,
+			infraProfile,
+			ssoProfile
+		});
 		try {
 			const { port, proxyAuthConfig } = await startTask();
 			updateToStatus({
@@ -48,7 +63,9 @@ const createTaskStore = () => {
 				name,
 				status: TaskStatus.RUNNING,
 				port,
-				proxyAuthConfig
+				proxyAuthConfigs: proxyAuthConfig ? [proxyAuthConfig] : proxyAuthConfigs,
+				infraProfile,
+				ssoProfile
 			});
 		} catch (e) {
 			console.warn('Failed to start task', e);
@@ -66,6 +83,37 @@ const createTaskStore = () => {
 	const stopTask = async (arn: string) => {
 		return invoke('stop_job', { arn });
 	};
-	return { subscribe: tasks.subscribe, startTask, stopTask };
+
+	const updateProxyAuthConfigs = (
+		arn: string,
+		update: (configs: ProxyAuthConfig[]) => ProxyAuthConfig[]
+	) => {
+		tasks.update((tasks) =>
+			tasks.map((t) =>
+				t.arn == arn ? { ...t, proxyAuthConfigs: update(t.proxyAuthConfigs ?? []) } : t
+			)
+		);
+	};
+
+	const addProxyAuthConfig = async (task: Task, proxyAuthConfig: ProxyAuthConfig) => {
+		const { arn, infraProfile, ssoProfile } = task;
+		await invoke('add_proxy_auth_config', { arn, infraProfile, ssoProfile, proxyAuthConfig });
+		updateProxyAuthConfigs(arn, (configs) => [
+			...configs.filter((c) => c.id != proxyAuthConfig.id),
+			proxyAuthConfig
+		]);
+	};
+
+	const removeProxyAuthConfig = async (arn: string, configId: number) => {
+		await invoke('remove_proxy_auth_config', { arn, configId });
+		updateProxyAuthConfigs(arn, (configs) => configs.filter((c) => c.id != configId));
+	};
+	return {
+		subscribe: tasks.subscribe,
+		startTask,
+		stopTask,
+		addProxyAuthConfig,
+		removeProxyAuthConfig
+	};
 };
 export const taskStore = createTaskStore();
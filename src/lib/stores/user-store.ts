@@ -1,6 +1,6 @@
 import { derived, writable } from 'svelte/store';
 import { execute } from './error-store';
-import type { AwsEnv, UserConfig, WombatAwsProfile } from '../types';
+import type { AwsEnv, LogNaming, LogWatchInput, UserConfig, WombatAwsProfile } from '../types';
 import { emit } from '@tauri-apps/api/event';
 
 const createUserStore = () => {
@@ -12,7 +12,9 @@ const createUserStore = () => {
 		last_used_profile: undefined,
 		logs_dir: '',
 		arn_to_proxy_port_map: {},
-		preferences: {}
+		preferences: {},
+		rest_api_port: 6891,
+		log_watches: []
 	});
 	execute<UserConfig>('user_config').then((config) => {
 		set(prepareConfig(config));
@@ -28,6 +30,11 @@ const createUserStore = () => {
 		set(prepareConfig(config));
 	};
 
+	const setRestApiPort = async (port: number) => {
+		const config = await execute<UserConfig>('set_rest_api_port', { port }, true);
+		set(prepareConfig(config));
+	};
+
 	const login = async (profile: WombatAwsProfile | undefined) => {
 		if (!profile) {
 			return;
@@ -54,13 +61,32 @@ const createUserStore = () => {
 		set(prepareConfig(config));
 	};
 
+	const setLogNaming = async (logNaming: LogNaming | null) => {
+		const config = await execute<UserConfig>('set_log_naming', { logNaming }, true);
+		set(prepareConfig(config));
+	};
+
+	const saveLogWatch = async (watch: LogWatchInput) => {
+		const config = await execute<UserConfig>('save_log_watch', { watch }, true);
+		set(prepareConfig(config));
+	};
+
+	const deleteLogWatch = async (id: string) => {
+		const config = await execute<UserConfig>('delete_log_watch', { id }, true);
+		set(prepareConfig(config));
+	};
+
 	return {
 		subscribe,
 		login,
 		setDbeaverPath,
 		setLogsDir,
+		setRestApiPort,
 		favoriteTrackedName,
-		savePreferredEnvs
+		savePreferredEnvs,
+		setLogNaming,
+		saveLogWatch,
+		deleteLogWatch
 	};
 };
 const prepareConfig = (config: UserConfig) => {
@@ -61,6 +61,7 @@ type EnvPortMap = { [key: string]: number };
 export type WombatProfilePreferences = {
 	preferred_environments: AwsEnv[];
 	tracked_names: string[];
+	log_naming: LogNaming | null;
 };
 export type UserConfig = {
 	id: string | undefined;
@@ -70,6 +71,30 @@ export type UserConfig = {
 	logs_dir: string;
 	arn_to_proxy_port_map: EnvPortMap;
 	preferences: { [key: string]: WombatProfilePreferences };
+	rest_api_port: number;
+	log_watches: LogWatch[];
+};
+
+export type LogWatchResult = {
+	checkedAt: string;
+	count: number;
+	triggered: boolean;
+	error: string | null;
+};
+export type LogWatch = {
+	id: string;
+	name: string;
+	apps: string[];
+	env: AwsEnv;
+	filter: string | null;
+	intervalMinutes: number;
+	threshold: number;
+	enabled: boolean;
+	history: LogWatchResult[];
+	consecutiveFailures: number;
+};
+export type LogWatchInput = Omit<LogWatch, 'id' | 'history' | 'consecutiveFailures'> & {
+	id: string | null;
 };
 
 export type EcsService = {
@@ -156,11 +181,24 @@ export type ProxyAuthConfig = {
 
 	basicUser: string | null | undefined;
 
+	jwtAlgorithm: string | null | undefined;
+	jwtClaims: { [key: string]: unknown } | null | undefined;
+	jwtTtlSeconds: number | null | undefined;
+	jwtPerRequest: boolean | null | undefined;
+
+	mtlsCert: string | null | undefined;
+	mtlsKey: string | null | undefined;
+
 	secretName: string;
 
 	requireSsoProfile: boolean;
 };
 
+export type ProxyAuthConfigs = {
+	configs: ProxyAuthConfig[];
+	invalid: CommandError[];
+};
+
 export type CustomHeader = {
 	name: string;
 	value: string;
@@ -193,4 +231,29 @@ export type LogFilter = {
 	filter: string;
 	services: string[];
 	label: string;
+	source: 'remote' | 'local';
+};
+export type LogFilterInput = Omit<LogFilter, 'id' | 'source'>;
+
+export type RestApiPairingCode = {
+	code: string;
+	expiresAt: string;
+};
+
+export type RestApiClient = {
+	id: string;
+	name: string;
+	createdAt: string;
+	lastUsedAt: string | null | undefined;
+};
+
+export type LogNamingRule = {
+	appType: string;
+	groupPrefix: string;
+	streamPrefix: string;
+	apps: string[];
+};
+
+export type LogNaming = {
+	rules: LogNamingRule[];
 };
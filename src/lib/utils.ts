@@ -1,4 +1,4 @@
-import type { AwsEnv, CommandError } from './types';
+import type { AwsEnv, CommandError, ProxyAuthConfig } from './types';
 
 export function* getFromList<T>(list: T[]): Generator<T> {
 	for (let i = 0; i < list.length; i++) {
@@ -14,6 +14,27 @@ export function cookieSessionProxyArn(address: string, env: AwsEnv): string {
 	return `wombat::cookieSessionProxy::${address}::${env.toLowerCase()}`;
 }
 
+export function proxyAuthConfigLabel(config: ProxyAuthConfig): string {
+	const path = config.apiPath && config.apiPath != '/' ? ` ${config.apiPath}` : '';
+	return `${config.authType}: ${config.jepsenClientId ?? config.basicUser ?? '?'}${path}`;
+}
+
+/** Reason the config cannot be used next to the already selected ones. */
+export function proxyAuthConfigConflict(
+	selected: ProxyAuthConfig[],
+	config: ProxyAuthConfig
+): string | undefined {
+	if (config.authType == 'mtls' && selected.some((c) => c.authType == 'mtls' && c.id != config.id)) {
+		return 'Only one mtls config can be used per proxy';
+	}
+	if (
+		config.authType != 'mtls' &&
+		selected.some((c) => c.authType != 'mtls' && c.apiPath == config.apiPath && c.id != config.id)
+	) {
+		return `Another config already handles ${config.apiPath}`;
+	}
+}
+
 export function isCommandError(e: unknown): e is CommandError {
 	return (e as CommandError).command != null && (e as CommandError).message != null;
 }